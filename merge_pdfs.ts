@@ -12,6 +12,7 @@ interface MergeOptions {
   overlay: string;
   output: string;
   flatten: boolean;
+  pageMap: number[] | null;
 }
 
 async function mergePdfs(options: MergeOptions): Promise<void> {
@@ -25,23 +26,27 @@ async function mergePdfs(options: MergeOptions): Promise<void> {
     const template = await PDFDocument.load(templateBytes);
     const overlay = await PDFDocument.load(overlayBytes);
     
-    // Merge pages
+    // Merge pages: overlay page i goes onto template page pageMap[i]
     const overlayPages = overlay.getPages();
     const templatePages = template.getPages();
+    const pageMap = options.pageMap ?? overlayPages.map((_, i) => i);
     
-    for (let i = 0; i < templatePages.length; i++) {
-      if (i < overlayPages.length) {
-        const [embeddedPage] = await template.embedPages([overlayPages[i]]);
-        const { width, height } = templatePages[i].getSize();
-        
-        // Draw overlay on template page
-        templatePages[i].drawPage(embeddedPage, {
-          x: 0,
-          y: 0,
-          width,
-          height,
-        });
+    for (let i = 0; i < overlayPages.length && i < pageMap.length; i++) {
+      const target = templatePages[pageMap[i]];
+      if (!target) {
+        continue;
       }
+      
+      const [embeddedPage] = await template.embedPages([overlayPages[i]]);
+      const { width, height } = target.getSize();
+      
+      // Draw overlay on template page
+      target.drawPage(embeddedPage, {
+        x: 0,
+        y: 0,
+        width,
+        height,
+      });
     }
     
     // Flatten form if requested
@@ -75,6 +80,7 @@ const options: MergeOptions = {
   overlay: '',
   output: '',
   flatten: false,
+  pageMap: null,
 };
 
 for (let i = 0; i < args.length; i++) {
@@ -91,11 +97,14 @@ for (let i = 0; i < args.length; i++) {
     case '--flatten':
       options.flatten = true;
       break;
+    case '--page-map':
+      options.pageMap = args[++i].split(',').filter(Boolean).map(Number);
+      break;
   }
 }
 
 if (!options.template || !options.overlay || !options.output) {
-  console.error('Usage: bun merge_pdfs.ts --template <file> --overlay <file> --output <file> [--flatten]');
+  console.error('Usage: bun merge_pdfs.ts --template <file> --overlay <file> --output <file> [--flatten] [--page-map 0,2,5]');
   process.exit(1);
 }
 
@@ -0,0 +1,865 @@
+use crate::fonts::encode_win_ansi;
+use crate::merge::{self, resolve_array, resolve_dict};
+use crate::metrics;
+use crate::types::{FieldData, FieldValue, ProcessingMetadata};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::BTreeMap;
+
+// Field flags (PDF 32000 tables 226, 228, 229), as bit masks
+const FF_MULTILINE: i64 = 1 << 12;
+const FF_RADIO: i64 = 1 << 15;
+const FF_PUSHBUTTON: i64 = 1 << 16;
+const FF_COMBO: i64 = 1 << 17;
+const FF_COMB: i64 = 1 << 24;
+
+/// Padding between the widget border and its text, in points.
+const TEXT_PADDING: f32 = 2.0;
+/// Font size used when the field's DA asks for auto size (`0 Tf`).
+const AUTO_FONT_SIZE_MAX: f32 = 12.0;
+const AUTO_FONT_SIZE_MIN: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FieldKind {
+    Text,
+    Checkbox,
+    Radio,
+    PushButton,
+    Choice,
+    Signature,
+    Unknown,
+}
+
+impl FieldKind {
+    /// The `field_type` a `FieldData` uses to fill this kind of field.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            FieldKind::Text => "text",
+            FieldKind::Checkbox => "checkbox",
+            FieldKind::Radio => "radio",
+            FieldKind::Choice => "dropdown",
+            FieldKind::Signature => "signature",
+            FieldKind::PushButton => "button",
+            FieldKind::Unknown => "unknown",
+        }
+    }
+}
+
+/// A terminal field of the template's form, with inheritable attributes resolved.
+pub(crate) struct FormField {
+    pub(crate) id: ObjectId,
+    pub(crate) kind: FieldKind,
+    pub(crate) flags: i64,
+    default_appearance: Option<String>,
+    quadding: i64,
+    pub(crate) max_len: Option<i64>,
+    /// `(export value, display text)` pairs from `/Opt`
+    pub(crate) options: Vec<(String, String)>,
+    pub(crate) widgets: Vec<ObjectId>,
+}
+
+/// Attributes a field inherits from its ancestors in the field tree.
+#[derive(Clone, Default)]
+struct Inherited {
+    field_type: Option<Vec<u8>>,
+    flags: i64,
+    default_appearance: Option<String>,
+    quadding: i64,
+    max_len: Option<i64>,
+    options: Option<Vec<Object>>,
+}
+
+/// Parsed `/DA` string: font resource name, size and colour operators.
+struct DefaultAppearance {
+    font: Vec<u8>,
+    size: f32,
+    color: String,
+}
+
+/// Set `/V` and generate appearances for template form fields addressed by
+/// their fully qualified name. Fields are flattened afterwards if requested.
+pub fn fill_form_document(
+    doc: &mut Document,
+    fields: &[FieldData],
+    flatten: bool,
+    metadata: &mut ProcessingMetadata,
+) -> anyhow::Result<()> {
+    let form_fields = collect_fields(doc);
+    if form_fields.is_empty() {
+        metadata.warnings.push("Template has no AcroForm fields; fields without coordinates were skipped".to_string());
+    }
+
+    for field in fields {
+        let Some(form_field) = form_fields.get(&field.field_id) else {
+            metadata.fields_skipped += 1;
+            if !form_fields.is_empty() {
+                metadata.warnings.push(format!("Form field {} not found in template", field.field_id));
+            }
+            continue;
+        };
+
+        let result = match form_field.kind {
+            FieldKind::Text => fill_text(doc, form_field, field, metadata),
+            FieldKind::Choice => fill_choice(doc, form_field, field, metadata),
+            FieldKind::Checkbox => fill_checkbox(doc, form_field, field),
+            FieldKind::Radio => fill_radio(doc, form_field, field),
+            FieldKind::PushButton | FieldKind::Signature | FieldKind::Unknown => Err(anyhow::anyhow!(
+                "field type is not fillable by name; give x/y/width/height to draw over it"
+            )),
+        };
+
+        match result {
+            Ok(()) => metadata.fields_processed += 1,
+            Err(e) => {
+                metadata.fields_skipped += 1;
+                metadata.errors.push(format!("Form field {}: {}", field.field_id, e));
+            }
+        }
+    }
+
+    if flatten {
+        merge::flatten_form(doc)?;
+        doc.prune_objects();
+    }
+
+    Ok(())
+}
+
+/// Map every terminal field's fully qualified name (`parent.child`) to the field.
+pub(crate) fn collect_fields(doc: &Document) -> BTreeMap<String, FormField> {
+    let mut fields = BTreeMap::new();
+
+    let Some(form) = doc.catalog().ok()
+        .and_then(|cat| cat.get(b"AcroForm").ok())
+        .and_then(|form| resolve_dict(doc, form))
+    else {
+        return fields;
+    };
+
+    let inherited = Inherited {
+        default_appearance: form.get(b"DA").ok().and_then(|da| da.as_str().ok())
+            .map(|da| String::from_utf8_lossy(da).into_owned()),
+        quadding: form.get(b"Q").and_then(Object::as_i64).unwrap_or(0),
+        ..Default::default()
+    };
+
+    let roots = form.get(b"Fields").ok()
+        .and_then(|f| resolve_array(doc, f))
+        .cloned()
+        .unwrap_or_default();
+
+    for root in roots {
+        if let Ok(id) = root.as_reference() {
+            walk_field(doc, id, None, &inherited, &mut fields, 0);
+        }
+    }
+
+    fields
+}
+
+fn walk_field(
+    doc: &Document,
+    id: ObjectId,
+    parent_name: Option<&str>,
+    inherited: &Inherited,
+    fields: &mut BTreeMap<String, FormField>,
+    depth: usize,
+) {
+    // Bounded in case of malformed /Kids cycles
+    if depth > 32 {
+        return;
+    }
+    let Ok(dict) = doc.get_dictionary(id) else {
+        return;
+    };
+
+    let partial = dict.get(b"T").ok().and_then(|t| lopdf::decode_text_string(t).ok());
+    let name = match (parent_name, &partial) {
+        (Some(parent), Some(partial)) => Some(format!("{}.{}", parent, partial)),
+        (None, Some(partial)) => Some(partial.clone()),
+        (parent, None) => parent.map(str::to_string),
+    };
+
+    let mut attrs = inherited.clone();
+    if let Ok(ft) = dict.get(b"FT").and_then(Object::as_name) {
+        attrs.field_type = Some(ft.to_vec());
+    }
+    if let Ok(ff) = dict.get(b"Ff").and_then(Object::as_i64) {
+        attrs.flags = ff;
+    }
+    if let Ok(da) = dict.get(b"DA").and_then(Object::as_str) {
+        attrs.default_appearance = Some(String::from_utf8_lossy(da).into_owned());
+    }
+    if let Ok(q) = dict.get(b"Q").and_then(Object::as_i64) {
+        attrs.quadding = q;
+    }
+    if let Ok(max_len) = dict.get(b"MaxLen").and_then(Object::as_i64) {
+        attrs.max_len = Some(max_len);
+    }
+    if let Some(opt) = dict.get(b"Opt").ok().and_then(|o| resolve_array(doc, o)) {
+        attrs.options = Some(opt.clone());
+    }
+
+    let kids: Vec<ObjectId> = dict.get(b"Kids").ok()
+        .and_then(|k| resolve_array(doc, k))
+        .map(|kids| kids.iter().filter_map(|k| k.as_reference().ok()).collect())
+        .unwrap_or_default();
+
+    // Kids carrying their own /T are child fields; kids without are this field's widgets
+    let child_fields: Vec<ObjectId> = kids.iter()
+        .copied()
+        .filter(|kid| doc.get_dictionary(*kid).map(|d| d.has(b"T")).unwrap_or(false))
+        .collect();
+
+    if !child_fields.is_empty() {
+        for kid in child_fields {
+            walk_field(doc, kid, name.as_deref(), &attrs, fields, depth + 1);
+        }
+        return;
+    }
+
+    let Some(name) = name else {
+        return;
+    };
+
+    let widgets = if kids.is_empty() { vec![id] } else { kids };
+
+    let kind = match attrs.field_type.as_deref() {
+        Some(b"Tx") => FieldKind::Text,
+        Some(b"Ch") => FieldKind::Choice,
+        Some(b"Sig") => FieldKind::Signature,
+        Some(b"Btn") if attrs.flags & FF_PUSHBUTTON != 0 => FieldKind::PushButton,
+        Some(b"Btn") if attrs.flags & FF_RADIO != 0 => FieldKind::Radio,
+        Some(b"Btn") => FieldKind::Checkbox,
+        _ => FieldKind::Unknown,
+    };
+
+    let options = attrs.options.as_deref()
+        .map(|opts| parse_options(doc, opts))
+        .unwrap_or_default();
+
+    fields.insert(name, FormField {
+        id,
+        kind,
+        flags: attrs.flags,
+        default_appearance: attrs.default_appearance,
+        quadding: attrs.quadding,
+        max_len: attrs.max_len,
+        options,
+        widgets,
+    });
+}
+
+/// `/Opt` entries are either text strings or `[export display]` pairs.
+fn parse_options(doc: &Document, opts: &[Object]) -> Vec<(String, String)> {
+    opts.iter()
+        .filter_map(|opt| {
+            let opt = match opt {
+                Object::Reference(id) => doc.get_object(*id).ok()?,
+                other => other,
+            };
+            match opt {
+                Object::Array(pair) if pair.len() >= 2 => {
+                    let export = lopdf::decode_text_string(&pair[0]).ok()?;
+                    let display = lopdf::decode_text_string(&pair[1]).ok()?;
+                    Some((export, display))
+                }
+                other => {
+                    let text = lopdf::decode_text_string(other).ok()?;
+                    Some((text.clone(), text))
+                }
+            }
+        })
+        .collect()
+}
+
+fn value_text(value: &FieldValue) -> anyhow::Result<String> {
+    match value {
+        FieldValue::Text(t) | FieldValue::Date(t) | FieldValue::Dropdown(t) | FieldValue::Radio(t) => Ok(t.clone()),
+        FieldValue::Number(n) => Ok(n.to_string()),
+        FieldValue::Checkbox(b) => Ok(if *b { "Yes" } else { "Off" }.to_string()),
+        FieldValue::Image(_) | FieldValue::Signature(_) => {
+            anyhow::bail!("images need x/y/width/height")
+        }
+        FieldValue::TypedSignature(_) => {
+            anyhow::bail!("typed signatures need x/y/width/height")
+        }
+        FieldValue::Qrcode(_) | FieldValue::Barcode(_) => {
+            anyhow::bail!("barcodes need x/y/width/height")
+        }
+        FieldValue::Line(_) | FieldValue::Rect(_) | FieldValue::Ellipse(_) | FieldValue::Highlight(_) => {
+            anyhow::bail!("shapes need x/y/width/height")
+        }
+    }
+}
+
+fn fill_text(doc: &mut Document, form_field: &FormField, field: &FieldData, metadata: &mut ProcessingMetadata) -> anyhow::Result<()> {
+    let text = value_text(&field.value)?;
+    doc.get_dictionary_mut(form_field.id)?.set("V", lopdf::text_string(&text));
+
+    for &widget in &form_field.widgets {
+        let lossless = set_text_appearance(doc, form_field, widget, field, &TextContent::Value(text.clone()))?;
+        if !lossless {
+            metadata.warnings.push(format!(
+                "Form field {} has characters its font cannot encode",
+                field.field_id
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn fill_choice(doc: &mut Document, form_field: &FormField, field: &FieldData, metadata: &mut ProcessingMetadata) -> anyhow::Result<()> {
+    let value = value_text(&field.value)?;
+
+    // Accept either the export value or the display text
+    let selected = form_field.options.iter()
+        .position(|(export, display)| *export == value || *display == value);
+
+    let export = match selected {
+        Some(index) => form_field.options[index].0.clone(),
+        None if form_field.options.is_empty() || form_field.flags & FF_COMBO != 0 => {
+            if !form_field.options.is_empty() {
+                metadata.warnings.push(format!(
+                    "Form field {}: '{}' is not one of its options",
+                    field.field_id, value
+                ));
+            }
+            value.clone()
+        }
+        None => anyhow::bail!("'{}' is not one of its options", value),
+    };
+
+    let field_dict = doc.get_dictionary_mut(form_field.id)?;
+    field_dict.set("V", lopdf::text_string(&export));
+    match selected {
+        Some(index) => field_dict.set("I", vec![Object::Integer(index as i64)]),
+        None => {
+            field_dict.remove(b"I");
+        }
+    }
+
+    let content = if form_field.flags & FF_COMBO != 0 {
+        let display = selected.map(|i| form_field.options[i].1.clone()).unwrap_or(value);
+        TextContent::Value(display)
+    } else {
+        TextContent::List(form_field.options.iter().map(|(_, d)| d.clone()).collect(), selected)
+    };
+
+    for &widget in &form_field.widgets {
+        let lossless = set_text_appearance(doc, form_field, widget, field, &content)?;
+        if !lossless {
+            metadata.warnings.push(format!(
+                "Form field {} has characters its font cannot encode",
+                field.field_id
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn fill_checkbox(doc: &mut Document, form_field: &FormField, field: &FieldData) -> anyhow::Result<()> {
+    let checked = match &field.value {
+        FieldValue::Checkbox(b) => *b,
+        other => {
+            let text = value_text(other)?;
+            let on_states: Vec<Vec<u8>> = form_field.widgets.iter()
+                .filter_map(|w| on_state(doc, *w))
+                .collect();
+            on_states.iter().any(|s| s == text.as_bytes())
+                || matches!(text.to_lowercase().as_str(), "true" | "yes" | "on" | "1" | "x")
+        }
+    };
+
+    let mut value = b"Off".to_vec();
+    for &widget in &form_field.widgets {
+        let on = match on_state(doc, widget) {
+            Some(on) => on,
+            None => {
+                add_check_appearance(doc, widget)?;
+                b"Yes".to_vec()
+            }
+        };
+        let state = if checked { on.clone() } else { b"Off".to_vec() };
+        if checked {
+            value = on;
+        }
+        doc.get_dictionary_mut(widget)?.set("AS", Object::Name(state));
+    }
+
+    doc.get_dictionary_mut(form_field.id)?.set("V", Object::Name(value));
+    Ok(())
+}
+
+fn fill_radio(doc: &mut Document, form_field: &FormField, field: &FieldData) -> anyhow::Result<()> {
+    let value = value_text(&field.value)?;
+
+    // Widgets match on their on-state name, or on the export value at their
+    // index in /Opt when the states are named by index
+    let on_states: Vec<Option<Vec<u8>>> = form_field.widgets.iter()
+        .map(|w| on_state(doc, *w))
+        .collect();
+    let chosen = on_states.iter()
+        .position(|s| s.as_deref() == Some(value.as_bytes()))
+        .or_else(|| form_field.options.iter().position(|(export, display)| *export == value || *display == value));
+
+    let Some(chosen) = chosen.filter(|&i| on_states.get(i).map(Option::is_some).unwrap_or(false)) else {
+        let available: Vec<String> = on_states.iter()
+            .flatten()
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .collect();
+        anyhow::bail!("'{}' is not one of its options ({})", value, available.join(", "));
+    };
+
+    let on = on_states[chosen].clone().unwrap_or_default();
+    for (i, &widget) in form_field.widgets.iter().enumerate() {
+        let state = if on_states[i].as_ref() == Some(&on) { on.clone() } else { b"Off".to_vec() };
+        doc.get_dictionary_mut(widget)?.set("AS", Object::Name(state));
+    }
+
+    doc.get_dictionary_mut(form_field.id)?.set("V", Object::Name(on));
+    Ok(())
+}
+
+/// Name of a button widget's "on" appearance state (any `/AP /N` key but `/Off`).
+pub(crate) fn on_state(doc: &Document, widget: ObjectId) -> Option<Vec<u8>> {
+    let dict = doc.get_dictionary(widget).ok()?;
+    let ap = dict.get(b"AP").ok().and_then(|ap| resolve_dict(doc, ap))?;
+    let normal = ap.get(b"N").ok().and_then(|n| resolve_dict(doc, n))?;
+    normal.iter()
+        .map(|(key, _)| key)
+        .find(|key| key.as_slice() != b"Off")
+        .cloned()
+}
+
+/// Give a checkbox widget without appearances a ZapfDingbats check and an empty off state.
+fn add_check_appearance(doc: &mut Document, widget: ObjectId) -> anyhow::Result<()> {
+    let (width, height, _) = widget_geometry(doc, widget)?;
+    let size = (width.min(height) * 0.8).max(1.0);
+
+    let mut font = Dictionary::new();
+    font.set("Type", "Font");
+    font.set("Subtype", "Type1");
+    font.set("BaseFont", "ZapfDingbats");
+    let mut fonts = Dictionary::new();
+    fonts.set("ZaDb", font);
+    let mut resources = Dictionary::new();
+    resources.set("Font", fonts);
+
+    let on_content = format!(
+        "q BT /ZaDb {} Tf 0 g {} {} Td (4) Tj ET Q",
+        size,
+        (width - size * 0.846) / 2.0,
+        (height - size * 0.7) / 2.0
+    );
+    let on = doc.add_object(appearance_stream(width, height, None, resources.clone(), on_content.into_bytes()));
+    let off = doc.add_object(appearance_stream(width, height, None, resources, Vec::new()));
+
+    let mut states = Dictionary::new();
+    states.set("Yes", Object::Reference(on));
+    states.set("Off", Object::Reference(off));
+    let mut ap = Dictionary::new();
+    ap.set("N", states);
+    doc.get_dictionary_mut(widget)?.set("AP", ap);
+    Ok(())
+}
+
+/// What a text-like appearance shows.
+enum TextContent {
+    /// A single value (text fields and combo boxes)
+    Value(String),
+    /// List box options, with the selected index highlighted
+    List(Vec<String>, Option<usize>),
+}
+
+/// Width and height of a widget's appearance box (rotation applied) and the
+/// `/MK /R` rotation in degrees.
+fn widget_geometry(doc: &Document, widget: ObjectId) -> anyhow::Result<(f32, f32, i64)> {
+    let dict = doc.get_dictionary(widget)?;
+    let rect = dict.get(b"Rect").ok()
+        .and_then(|r| resolve_array(doc, r))
+        .filter(|r| r.len() >= 4)
+        .ok_or_else(|| anyhow::anyhow!("widget has no /Rect"))?;
+    let coords: Vec<f32> = rect.iter().take(4).map(|o| o.as_float().unwrap_or(0.0)).collect();
+    let width = (coords[2] - coords[0]).abs();
+    let height = (coords[3] - coords[1]).abs();
+
+    let rotation = dict.get(b"MK").ok()
+        .and_then(|mk| resolve_dict(doc, mk))
+        .and_then(|mk| mk.get(b"R").and_then(Object::as_i64).ok())
+        .unwrap_or(0)
+        .rem_euclid(360);
+
+    Ok(match rotation {
+        90 | 270 => (height, width, rotation),
+        _ => (width, height, rotation),
+    })
+}
+
+fn appearance_stream(width: f32, height: f32, matrix: Option<[f32; 6]>, resources: Dictionary, content: Vec<u8>) -> Stream {
+    let mut dict = Dictionary::new();
+    dict.set("Type", "XObject");
+    dict.set("Subtype", "Form");
+    dict.set("BBox", vec![0.into(), 0.into(), width.into(), height.into()]);
+    if let Some(m) = matrix {
+        dict.set("Matrix", m.iter().map(|&v| Object::Real(v)).collect::<Vec<_>>());
+    }
+    dict.set("Resources", resources);
+    let mut stream = Stream::new(dict, content);
+    let _ = stream.compress();
+    stream
+}
+
+/// Generate and attach a widget's normal appearance for text-like content.
+/// Returns false if some characters could not be encoded in the field's font.
+fn set_text_appearance(
+    doc: &mut Document,
+    form_field: &FormField,
+    widget: ObjectId,
+    field: &FieldData,
+    content: &TextContent,
+) -> anyhow::Result<bool> {
+    let (width, height, rotation) = widget_geometry(doc, widget)?;
+
+    let da_source = doc.get_dictionary(widget).ok()
+        .and_then(|w| w.get(b"DA").and_then(Object::as_str).ok())
+        .map(|da| String::from_utf8_lossy(da).into_owned())
+        .or_else(|| form_field.default_appearance.clone())
+        .unwrap_or_else(|| "/Helv 0 Tf 0 g".to_string());
+    let mut da = parse_default_appearance(&da_source);
+    if let Some(size) = field.font_size {
+        da.size = size;
+    }
+
+    let (font_name, font_object, font) = resolve_font(doc, &da.font);
+
+    let mut ops = String::new();
+    ops.push_str(&widget_decoration(doc, widget, width, height));
+
+    let inner_width = (width - 2.0 * TEXT_PADDING).max(0.0);
+    let inner_height = (height - 2.0 * TEXT_PADDING).max(0.0);
+    let mut lossless = true;
+
+    ops.push_str("/Tx BMC\nq\n");
+    ops.push_str(&format!(
+        "{} {} {} {} re W n\n",
+        TEXT_PADDING, TEXT_PADDING, inner_width, inner_height
+    ));
+
+    let quadding = match field.alignment.as_deref() {
+        Some("center") => 1,
+        Some("right") => 2,
+        Some(_) => 0,
+        None => form_field.quadding,
+    };
+    let align = |line_width: f32| match quadding {
+        1 => TEXT_PADDING + (inner_width - line_width) / 2.0,
+        2 => TEXT_PADDING + inner_width - line_width,
+        _ => TEXT_PADDING,
+    };
+
+    match content {
+        TextContent::Value(text) if form_field.flags & FF_COMB != 0 && form_field.max_len.unwrap_or(0) > 0 => {
+            let cells = form_field.max_len.unwrap_or(1) as usize;
+            let cell = width / cells as f32;
+            let size = if da.size > 0.0 { da.size } else { auto_single_line_size(height) };
+            let baseline = (height - size * 0.925) / 2.0 + size * 0.207;
+
+            ops.push_str(&format!("BT\n/{} {} Tf\n{}\n", String::from_utf8_lossy(&font_name), size, da.color));
+            for (i, c) in text.chars().take(cells).enumerate() {
+                let glyph = c.to_string();
+                let (bytes, ok) = encode_win_ansi(&glyph);
+                lossless &= ok;
+                let x = i as f32 * cell + (cell - font.width(&glyph, size)) / 2.0;
+                ops.push_str(&format!("1 0 0 1 {} {} Tm\n{} Tj\n", x, baseline, pdf_string(&bytes)));
+            }
+            ops.push_str("ET\n");
+        }
+        TextContent::Value(text) if form_field.flags & FF_MULTILINE != 0 => {
+            let size = if da.size > 0.0 {
+                da.size
+            } else {
+                auto_multiline_size(text, inner_width, inner_height, &font)
+            };
+            let line_height = size * 1.2;
+            let lines = wrap_lines(text, inner_width, size, &font);
+
+            ops.push_str(&format!("BT\n/{} {} Tf\n{}\n", String::from_utf8_lossy(&font_name), size, da.color));
+            let mut y = height - TEXT_PADDING - size * 0.9;
+            for line in lines {
+                let (bytes, ok) = encode_win_ansi(&line);
+                lossless &= ok;
+                let x = align(font.width(&line, size));
+                ops.push_str(&format!("1 0 0 1 {} {} Tm\n{} Tj\n", x, y, pdf_string(&bytes)));
+                y -= line_height;
+            }
+            ops.push_str("ET\n");
+        }
+        TextContent::Value(text) => {
+            let text_width = |size: f32| font.width(text, size);
+            let size = if da.size > 0.0 {
+                da.size
+            } else {
+                let size = auto_single_line_size(height);
+                let measured = text_width(size);
+                if measured > inner_width && measured > 0.0 {
+                    (size * inner_width / measured).max(AUTO_FONT_SIZE_MIN)
+                } else {
+                    size
+                }
+            };
+            let (bytes, ok) = encode_win_ansi(text);
+            lossless &= ok;
+            let baseline = (height - size * 0.925) / 2.0 + size * 0.207;
+
+            ops.push_str(&format!(
+                "BT\n/{} {} Tf\n{}\n1 0 0 1 {} {} Tm\n{} Tj\nET\n",
+                String::from_utf8_lossy(&font_name), size, da.color,
+                align(text_width(size)), baseline, pdf_string(&bytes)
+            ));
+        }
+        TextContent::List(items, selected) => {
+            let size = if da.size > 0.0 { da.size } else { AUTO_FONT_SIZE_MAX };
+            let line_height = size * 1.2;
+            let top = height - TEXT_PADDING;
+
+            if let Some(index) = selected {
+                let y = top - line_height * (*index as f32 + 1.0);
+                ops.push_str(&format!(
+                    "0.6 0.75 0.86 rg\n{} {} {} {} re f\n",
+                    TEXT_PADDING, y, inner_width, line_height
+                ));
+            }
+
+            ops.push_str(&format!("BT\n/{} {} Tf\n{}\n", String::from_utf8_lossy(&font_name), size, da.color));
+            for (i, item) in items.iter().enumerate() {
+                let (bytes, ok) = encode_win_ansi(item);
+                lossless &= ok;
+                let y = top - line_height * (i as f32 + 1.0) + size * 0.25;
+                ops.push_str(&format!("1 0 0 1 {} {} Tm\n{} Tj\n", align(font.width(item, size)), y, pdf_string(&bytes)));
+            }
+            ops.push_str("ET\n");
+        }
+    }
+    ops.push_str("Q\nEMC\n");
+
+    let mut fonts = Dictionary::new();
+    fonts.set(font_name, font_object);
+    let mut resources = Dictionary::new();
+    resources.set("Font", fonts);
+
+    let matrix = match rotation {
+        90 => Some([0.0, 1.0, -1.0, 0.0, 0.0, 0.0]),
+        180 => Some([-1.0, 0.0, 0.0, -1.0, 0.0, 0.0]),
+        270 => Some([0.0, -1.0, 1.0, 0.0, 0.0, 0.0]),
+        _ => None,
+    };
+
+    let stream_id = doc.add_object(appearance_stream(width, height, matrix, resources, ops.into_bytes()));
+    let mut ap = Dictionary::new();
+    ap.set("N", Object::Reference(stream_id));
+    doc.get_dictionary_mut(widget)?.set("AP", ap);
+    Ok(lossless)
+}
+
+/// Background and border from the widget's `/MK` dictionary.
+fn widget_decoration(doc: &Document, widget: ObjectId, width: f32, height: f32) -> String {
+    let Ok(dict) = doc.get_dictionary(widget) else {
+        return String::new();
+    };
+    let Some(mk) = dict.get(b"MK").ok().and_then(|mk| resolve_dict(doc, mk)) else {
+        return String::new();
+    };
+
+    let mut ops = String::new();
+    if let Some(fill) = mk.get(b"BG").ok().and_then(|c| color_operator(doc, c, false)) {
+        ops.push_str(&format!("{}\n0 0 {} {} re f\n", fill, width, height));
+    }
+
+    let border_width = dict.get(b"BS").ok()
+        .and_then(|bs| resolve_dict(doc, bs))
+        .and_then(|bs| bs.get(b"W").ok().and_then(|w| w.as_float().ok()))
+        .unwrap_or(1.0);
+    if border_width > 0.0 {
+        if let Some(stroke) = mk.get(b"BC").ok().and_then(|c| color_operator(doc, c, true)) {
+            ops.push_str(&format!(
+                "{}\n{} w\n{} {} {} {} re S\n",
+                stroke, border_width,
+                border_width / 2.0, border_width / 2.0,
+                width - border_width, height - border_width
+            ));
+        }
+    }
+    ops
+}
+
+/// `g`/`rg`/`k` operator for a colour array of 1, 3 or 4 components.
+fn color_operator(doc: &Document, color: &Object, stroke: bool) -> Option<String> {
+    let components: Vec<f32> = resolve_array(doc, color)?
+        .iter()
+        .filter_map(|c| c.as_float().ok())
+        .collect();
+    let op = match (components.len(), stroke) {
+        (1, false) => "g",
+        (1, true) => "G",
+        (3, false) => "rg",
+        (3, true) => "RG",
+        (4, false) => "k",
+        (4, true) => "K",
+        _ => return None,
+    };
+    let values: Vec<String> = components.iter().map(|c| c.to_string()).collect();
+    Some(format!("{} {}", values.join(" "), op))
+}
+
+fn parse_default_appearance(da: &str) -> DefaultAppearance {
+    let tokens: Vec<&str> = da.split_whitespace().collect();
+
+    let (font, size) = tokens.iter()
+        .position(|&t| t == "Tf")
+        .filter(|&i| i >= 2)
+        .map(|i| {
+            let font = tokens[i - 2].trim_start_matches('/').as_bytes().to_vec();
+            let size = tokens[i - 1].parse().unwrap_or(0.0);
+            (font, size)
+        })
+        .unwrap_or_else(|| (b"Helv".to_vec(), 0.0));
+
+    let color = tokens.iter()
+        .rposition(|&t| matches!(t, "g" | "rg" | "k"))
+        .and_then(|i| {
+            let operands = match tokens[i] {
+                "g" => 1,
+                "rg" => 3,
+                _ => 4,
+            };
+            (i >= operands).then(|| tokens[i - operands..=i].join(" "))
+        })
+        .unwrap_or_else(|| "0 g".to_string());
+
+    DefaultAppearance { font, size, color }
+}
+
+/// Widths of a simple font from its `/Widths` array, falling back to the
+/// base-14 metrics for standard fonts without one.
+struct FontWidths {
+    first_char: i64,
+    widths: Vec<f32>,
+    fixed_pitch: bool,
+}
+
+impl FontWidths {
+    fn width(&self, text: &str, size: f32) -> f32 {
+        if self.fixed_pitch {
+            return text.chars().count() as f32 * 0.6 * size;
+        }
+        if self.widths.is_empty() {
+            return metrics::helvetica_width(text, size);
+        }
+        let (bytes, _) = encode_win_ansi(text);
+        let units: f32 = bytes.iter()
+            .map(|&b| {
+                usize::try_from(b as i64 - self.first_char).ok()
+                    .and_then(|i| self.widths.get(i).copied())
+                    .unwrap_or(500.0)
+            })
+            .sum();
+        units / 1000.0 * size
+    }
+}
+
+/// Find the DA font in the form's `/DR`. Composite or missing fonts are
+/// replaced by Helvetica, which the generated appearance can always encode.
+fn resolve_font(doc: &Document, name: &[u8]) -> (Vec<u8>, Object, FontWidths) {
+    let from_dr = doc.catalog().ok()
+        .and_then(|cat| cat.get(b"AcroForm").ok())
+        .and_then(|form| resolve_dict(doc, form))
+        .and_then(|form| form.get(b"DR").ok())
+        .and_then(|dr| resolve_dict(doc, dr))
+        .and_then(|dr| dr.get(b"Font").ok())
+        .and_then(|fonts| resolve_dict(doc, fonts))
+        .and_then(|fonts| fonts.get(name).ok().cloned());
+
+    if let Some(object) = from_dr {
+        if let Some(font) = resolve_dict(doc, &object) {
+            let simple = font.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Type0".as_slice());
+            if simple {
+                let base_font = font.get(b"BaseFont").and_then(Object::as_name).unwrap_or(b"");
+                let widths = font.get(b"Widths").ok()
+                    .and_then(|w| resolve_array(doc, w))
+                    .map(|w| w.iter().map(|v| v.as_float().unwrap_or(0.0)).collect())
+                    .unwrap_or_default();
+                let metrics = FontWidths {
+                    first_char: font.get(b"FirstChar").and_then(Object::as_i64).unwrap_or(0),
+                    widths,
+                    fixed_pitch: base_font.starts_with(b"Courier"),
+                };
+                return (name.to_vec(), object, metrics);
+            }
+        }
+    }
+
+    let mut helvetica = Dictionary::new();
+    helvetica.set("Type", "Font");
+    helvetica.set("Subtype", "Type1");
+    helvetica.set("BaseFont", "Helvetica");
+    helvetica.set("Encoding", "WinAnsiEncoding");
+    let metrics = FontWidths { first_char: 0, widths: Vec::new(), fixed_pitch: false };
+    (b"FillPdfHelv".to_vec(), Object::Dictionary(helvetica), metrics)
+}
+
+fn auto_single_line_size(height: f32) -> f32 {
+    ((height - 2.0 * TEXT_PADDING) / 1.15).clamp(AUTO_FONT_SIZE_MIN, AUTO_FONT_SIZE_MAX)
+}
+
+/// Largest size (in half-point steps) whose wrapped lines fit the box.
+fn auto_multiline_size(text: &str, width: f32, height: f32, font: &FontWidths) -> f32 {
+    let mut size = AUTO_FONT_SIZE_MAX;
+    while size > AUTO_FONT_SIZE_MIN {
+        let lines = wrap_lines(text, width, size, font);
+        if lines.len() as f32 * size * 1.2 <= height {
+            break;
+        }
+        size -= 0.5;
+    }
+    size
+}
+
+/// Break text into lines at newlines and between words so each fits `width`.
+fn wrap_lines(text: &str, width: f32, size: f32, font: &FontWidths) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut current = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if current.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", current, word)
+            };
+            if font.width(&candidate, size) <= width || current.is_empty() {
+                current = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut current, word.to_string()));
+            }
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+/// Literal PDF string with `(`, `)` and `\` escaped.
+fn pdf_string(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('(');
+    for &b in bytes {
+        match b {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(b as char);
+            }
+            0x20..=0x7E => out.push(b as char),
+            _ => out.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    out.push(')');
+    out
+}
@@ -0,0 +1,239 @@
+use crate::types::{ErrorCorrection, QrCodeValue};
+use pdf_writer::Content;
+use qrcodegen::{QrCode, QrCodeEcc};
+
+/// Bar and space widths of Code 128 symbols 0-105, starting with a bar.
+const CODE128_PATTERNS: [&[u8; 6]; 106] = [
+    b"212222", b"222122", b"222221", b"121223", b"121322", b"131222", b"122213", b"122312", b"132212", b"221213",
+    b"221312", b"231212", b"112232", b"122132", b"122231", b"113222", b"123122", b"123221", b"223211", b"221132",
+    b"221231", b"213212", b"223112", b"312131", b"311222", b"321122", b"321221", b"312212", b"322112", b"322211",
+    b"212123", b"212321", b"232121", b"111323", b"131123", b"131321", b"112313", b"132113", b"132311", b"211313",
+    b"231113", b"231311", b"112133", b"112331", b"132131", b"113123", b"113321", b"133121", b"313121", b"211331",
+    b"231131", b"213113", b"213311", b"213131", b"311123", b"311321", b"331121", b"312113", b"312311", b"332111",
+    b"314111", b"221411", b"431111", b"111224", b"111422", b"121124", b"121421", b"141122", b"141221", b"112214",
+    b"112412", b"122114", b"122411", b"142112", b"142211", b"241211", b"221114", b"413111", b"241112", b"134111",
+    b"111242", b"121142", b"121241", b"114212", b"124112", b"124211", b"411212", b"421112", b"421211", b"212141",
+    b"214121", b"412121", b"111143", b"111341", b"131141", b"114113", b"114311", b"411113", b"411311", b"113141",
+    b"114131", b"311141", b"411131", b"211412", b"211214", b"211232",
+];
+const CODE128_STOP: &[u8; 7] = b"2331112";
+const CODE128_START_B: u8 = 104;
+const CODE128_START_C: u8 = 105;
+
+/// EAN-13 left-hand "L" codes per digit; "R" codes are their complement and
+/// "G" codes the reversed "R" codes.
+const EAN_L_CODES: [&[u8; 7]; 10] = [
+    b"0001101", b"0011001", b"0010011", b"0111101", b"0100011",
+    b"0110001", b"0101111", b"0111011", b"0110111", b"0001011",
+];
+/// Which of the six left digits use "G" codes, encoding the first digit.
+const EAN_PARITY: [&[u8; 6]; 10] = [
+    b"LLLLLL", b"LLGLGG", b"LLGGLG", b"LLGGGL", b"LGLLGG",
+    b"LGGLLG", b"LGGGLL", b"LGLGLG", b"LGLGGL", b"LGGLGL",
+];
+
+/// QR code modules, row by row from the top, dark as `true`.
+pub fn qr_code(value: &QrCodeValue) -> anyhow::Result<Vec<Vec<bool>>> {
+    let ecc = match value.error_correction {
+        ErrorCorrection::Low => QrCodeEcc::Low,
+        ErrorCorrection::Medium => QrCodeEcc::Medium,
+        ErrorCorrection::Quartile => QrCodeEcc::Quartile,
+        ErrorCorrection::High => QrCodeEcc::High,
+    };
+    let qr = QrCode::encode_text(&value.data, ecc)
+        .map_err(|_| anyhow::anyhow!("{} bytes is too much data for a QR code", value.data.len()))?;
+    Ok((0..qr.size())
+        .map(|y| (0..qr.size()).map(|x| qr.get_module(x, y)).collect())
+        .collect())
+}
+
+/// Code 128 bars in narrow-bar modules. All-digit data of even length uses
+/// code set C (two digits per symbol), anything else code set B.
+pub fn code128(data: &str) -> anyhow::Result<Vec<bool>> {
+    let digits = data.len().is_multiple_of(2) && data.bytes().all(|b| b.is_ascii_digit());
+    let mut codes = Vec::new();
+    if data.is_empty() {
+        anyhow::bail!("Code 128 data is empty");
+    } else if digits {
+        codes.push(CODE128_START_C);
+        codes.extend(data.as_bytes().chunks(2).map(|pair| (pair[0] - b'0') * 10 + (pair[1] - b'0')));
+    } else {
+        codes.push(CODE128_START_B);
+        for c in data.chars() {
+            if !(' '..='~').contains(&c) {
+                anyhow::bail!("Code 128 cannot encode '{}'", c);
+            }
+            codes.push(c as u8 - b' ');
+        }
+    }
+
+    let checksum = codes.iter().enumerate()
+        .map(|(i, &code)| i.max(1) as u32 * code as u32)
+        .sum::<u32>() % 103;
+    codes.push(checksum as u8);
+
+    let mut modules = Vec::new();
+    for code in codes {
+        push_widths(&mut modules, CODE128_PATTERNS[code as usize]);
+    }
+    push_widths(&mut modules, CODE128_STOP);
+    Ok(modules)
+}
+
+/// EAN-13 bars in modules, and the full 13 digits. A 12-digit `data` gets its
+/// check digit computed; a 13-digit one must have the right one.
+pub fn ean13(data: &str) -> anyhow::Result<(Vec<bool>, String)> {
+    let digits: Vec<u8> = data.bytes().map(|b| b.wrapping_sub(b'0')).collect();
+    if !matches!(digits.len(), 12 | 13) || digits.iter().any(|&d| d > 9) {
+        anyhow::bail!("EAN-13 needs 12 or 13 digits, got '{}'", data);
+    }
+    let check = (10 - digits[..12].iter().enumerate()
+        .map(|(i, &d)| if i % 2 == 0 { d as u32 } else { d as u32 * 3 })
+        .sum::<u32>() % 10) % 10;
+    match digits.get(12) {
+        Some(&given) if given as u32 != check => {
+            anyhow::bail!("EAN-13 check digit of '{}' should be {}", data, check);
+        }
+        _ => {}
+    }
+
+    let mut modules = Vec::new();
+    let mut push_bits = |bits: &[u8]| modules.extend(bits.iter().map(|&b| b == b'1'));
+    push_bits(b"101");
+    for (i, &digit) in digits[1..7].iter().enumerate() {
+        let l_code = EAN_L_CODES[digit as usize];
+        if EAN_PARITY[digits[0] as usize][i] == b'G' {
+            let mut g_code = complement(l_code);
+            g_code.reverse();
+            push_bits(&g_code);
+        } else {
+            push_bits(l_code);
+        }
+    }
+    push_bits(b"01010");
+    for &digit in &digits[7..12] {
+        push_bits(&complement(EAN_L_CODES[digit as usize]));
+    }
+    push_bits(&complement(EAN_L_CODES[check as usize]));
+    push_bits(b"101");
+
+    let text = format!("{}{}", &data[..12], check);
+    Ok((modules, text))
+}
+
+/// Fill the dark modules as rectangles, merging runs within each row. `x`
+/// and `top` are the symbol's top-left corner in PDF coordinates.
+pub fn draw_modules(content: &mut Content, rows: &[Vec<bool>], x: f32, top: f32, module_width: f32, module_height: f32) {
+    for (row_index, row) in rows.iter().enumerate() {
+        let y = top - (row_index + 1) as f32 * module_height;
+        let mut column = 0;
+        while column < row.len() {
+            if !row[column] {
+                column += 1;
+                continue;
+            }
+            let start = column;
+            while column < row.len() && row[column] {
+                column += 1;
+            }
+            content.rect(x + start as f32 * module_width, y, (column - start) as f32 * module_width, module_height);
+        }
+    }
+    content.fill_nonzero();
+}
+
+fn push_widths(modules: &mut Vec<bool>, widths: &[u8]) {
+    for (i, &width) in widths.iter().enumerate() {
+        let bar = i % 2 == 0;
+        modules.extend(std::iter::repeat_n(bar, (width - b'0') as usize));
+    }
+}
+
+fn complement(code: &[u8; 7]) -> Vec<u8> {
+    code.iter().map(|&b| if b == b'1' { b'0' } else { b'1' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(pattern: &str) -> Vec<bool> {
+        pattern.bytes().filter(|b| *b != b' ').map(|b| b == b'1').collect()
+    }
+
+    /// Bars and spaces from widths, starting with a bar.
+    fn widths(pattern: &str) -> Vec<bool> {
+        pattern.bytes().filter(|b| *b != b' ').enumerate()
+            .flat_map(|(i, w)| std::iter::repeat_n(i % 2 == 0, (w - b'0') as usize))
+            .collect()
+    }
+
+    #[test]
+    fn ean13_computes_check_digit_and_parity() {
+        let (modules, text) = ean13("400638133393").unwrap();
+        assert_eq!(text, "4006381333931");
+        // First digit 4 sets the left parity to LGLLGG
+        let expected = bits(
+            "101 0001101 0100111 0101111 0111101 0001001 0110011 \
+             01010 1000010 1000010 1000010 1110100 1000010 1100110 101",
+        );
+        assert_eq!(modules, expected);
+    }
+
+    #[test]
+    fn ean13_encodes_a_six_prefixed_code() {
+        let (modules, text) = ean13("690123456789").unwrap();
+        assert_eq!(text, "6901234567892");
+        // First digit 6 sets the left parity to LGGGLL
+        let expected = bits(
+            "101 0001011 0100111 0110011 0011011 0111101 0100011 \
+             01010 1001110 1010000 1000100 1001000 1110100 1101100 101",
+        );
+        assert_eq!(modules, expected);
+    }
+
+    #[test]
+    fn ean13_parity_follows_the_first_digit() {
+        let parities = [
+            "LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG",
+            "LGGLLG", "LGGGLL", "LGLGLG", "LGLGGL", "LGGLGL",
+        ];
+        for (first, parity) in parities.iter().enumerate() {
+            // Six zeros on the left: L code 0001101, G code 0100111
+            let (modules, _) = ean13(&format!("{}00000000000", first)).unwrap();
+            let left: String = parity.chars()
+                .map(|p| if p == 'L' { "0001101" } else { "0100111" })
+                .collect();
+            assert_eq!(modules[3..45], bits(&left), "first digit {}", first);
+        }
+    }
+
+    #[test]
+    fn ean13_rejects_bad_input() {
+        assert!(ean13("4006381333932").is_err());
+        assert!(ean13("40063813339").is_err());
+        assert!(ean13("40063813339a").is_err());
+    }
+
+    #[test]
+    fn code128_uses_set_c_for_digit_pairs() {
+        // Start C, 12, 34, checksum (105 + 12 + 2 * 34) % 103 = 82, stop
+        let expected = widths("211232 112232 131123 121241 2331112");
+        assert_eq!(code128("1234").unwrap(), expected);
+    }
+
+    #[test]
+    fn code128_uses_set_b_for_text() {
+        // Start B, P J J 1 2 3 C, checksum 879 % 103 = 55, stop
+        let expected = widths(
+            "211214 313121 112133 112133 123221 223211 221132 131321 311321 2331112",
+        );
+        assert_eq!(code128("PJJ123C").unwrap(), expected);
+    }
+
+    #[test]
+    fn code128_rejects_unencodable_data() {
+        assert!(code128("").is_err());
+        assert!(code128("café").is_err());
+    }
+}
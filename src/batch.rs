@@ -0,0 +1,197 @@
+use crate::types::{BarcodeValue, FieldData, FieldValue, ImageSource, QrCodeValue, TypedSignature, UrlConfig};
+use serde_json::Value;
+use std::path::Path;
+
+/// Read records from a CSV file (header row = field ids), a JSON array, or
+/// JSON Lines. Each record is either an object of `field_id -> value` or a
+/// complete `FieldData` array.
+pub fn read_records(path: &Path) -> anyhow::Result<Vec<Value>> {
+    let extension = path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "csv" => read_csv(path),
+        "json" => {
+            let data = std::fs::read_to_string(path)?;
+            match serde_json::from_str(&data)? {
+                Value::Array(records) => Ok(records),
+                _ => anyhow::bail!("{} must contain a JSON array of records", path.display()),
+            }
+        }
+        _ => {
+            let data = std::fs::read_to_string(path)?;
+            data.lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(i, line)| {
+                    serde_json::from_str(line)
+                        .map_err(|e| anyhow::anyhow!("Invalid JSON on line {}: {}", i + 1, e))
+                })
+                .collect()
+        }
+    }
+}
+
+fn read_csv(path: &Path) -> anyhow::Result<Vec<Value>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let headers = reader.headers()?.clone();
+
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        let record: serde_json::Map<String, Value> = headers.iter()
+            .zip(row.iter())
+            .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+            .collect();
+        records.push(Value::Object(record));
+    }
+    Ok(records)
+}
+
+/// Field data for one record.
+///
+/// Object records fill the matching `layout` fields, converting values to each
+/// field's type; blank values leave the field out. Keys not in the layout fill
+/// the template form field of that name.
+pub fn record_fields(record: &Value, layout: &[FieldData]) -> anyhow::Result<Vec<FieldData>> {
+    let values = match record {
+        Value::Array(_) => return Ok(serde_json::from_value(record.clone())?),
+        Value::Object(values) => values,
+        _ => anyhow::bail!("Record must be a JSON object or a FieldData array"),
+    };
+
+    let mut fields = Vec::with_capacity(layout.len());
+    for field in layout {
+        match values.get(&field.field_id) {
+            None => fields.push(field.clone()),
+            Some(raw) if is_blank(raw) => {}
+            Some(raw) => {
+                let mut field = field.clone();
+                field.value = convert_value(&field.value, raw)
+                    .map_err(|e| anyhow::anyhow!("Field {}: {}", field.field_id, e))?;
+                fields.push(field);
+            }
+        }
+    }
+
+    for (key, raw) in values {
+        if is_blank(raw) || layout.iter().any(|f| f.field_id == *key) {
+            continue;
+        }
+        let value = match raw {
+            Value::Bool(b) => FieldValue::Checkbox(*b),
+            Value::Number(n) => FieldValue::Number(n.as_f64().unwrap_or_default()),
+            Value::String(s) => FieldValue::Text(s.clone()),
+            other => serde_json::from_value(other.clone())
+                .map_err(|e| anyhow::anyhow!("Field {}: {}", key, e))?,
+        };
+        fields.push(FieldData::named(key.clone(), value));
+    }
+
+    Ok(fields)
+}
+
+fn is_blank(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+/// Convert a raw record value to the same `FieldValue` variant as the layout's.
+fn convert_value(template: &FieldValue, raw: &Value) -> anyhow::Result<FieldValue> {
+    // An explicit {"field_type": ..., "value": ...} object wins
+    if raw.is_object() {
+        return Ok(serde_json::from_value(raw.clone())?);
+    }
+
+    let text = match raw {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    Ok(match template {
+        FieldValue::Text(_) => FieldValue::Text(text),
+        FieldValue::Date(_) => FieldValue::Date(text),
+        FieldValue::Radio(_) => FieldValue::Radio(text),
+        FieldValue::Dropdown(_) => FieldValue::Dropdown(text),
+        FieldValue::Number(_) => match raw {
+            Value::Number(n) => FieldValue::Number(n.as_f64().unwrap_or_default()),
+            _ => FieldValue::Number(text.trim().parse()
+                .map_err(|_| anyhow::anyhow!("'{}' is not a number", text))?),
+        },
+        FieldValue::Checkbox(_) => match raw {
+            Value::Bool(b) => FieldValue::Checkbox(*b),
+            _ => FieldValue::Checkbox(matches!(
+                text.trim().to_lowercase().as_str(),
+                "true" | "yes" | "y" | "1" | "x" | "on" | "checked"
+            )),
+        },
+        FieldValue::Image(_) => FieldValue::Image(image_source(text)),
+        FieldValue::Signature(_) => FieldValue::Signature(image_source(text)),
+        FieldValue::TypedSignature(signature) => FieldValue::TypedSignature(TypedSignature {
+            name: text,
+            ..signature.clone()
+        }),
+        FieldValue::Qrcode(code) => FieldValue::Qrcode(QrCodeValue {
+            data: text,
+            ..code.clone()
+        }),
+        FieldValue::Barcode(code) => FieldValue::Barcode(BarcodeValue {
+            data: text,
+            ..code.clone()
+        }),
+        // Shapes carry no data; a non-blank value just keeps them
+        FieldValue::Line(_) | FieldValue::Rect(_) | FieldValue::Ellipse(_) | FieldValue::Highlight(_) => template.clone(),
+    })
+}
+
+fn image_source(text: String) -> ImageSource {
+    if text.starts_with("http://") || text.starts_with("https://") {
+        ImageSource::Url(UrlConfig {
+            url: text,
+            method: None,
+            headers: None,
+            body: None,
+        })
+    } else {
+        ImageSource::Base64(text)
+    }
+}
+
+/// Expand an output filename pattern. `{index}` is the 1-based record number
+/// and `{key}` is the record's value for `key`, made safe for file names.
+pub fn output_name(pattern: &str, index: usize, record: &Value) -> anyhow::Result<String> {
+    let mut name = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after.find('}')
+            .ok_or_else(|| anyhow::anyhow!("Unclosed '{{' in output pattern"))?;
+        let key = &after[..end];
+
+        if key == "index" {
+            name.push_str(&index.to_string());
+        } else {
+            let value = record.get(key)
+                .filter(|v| !is_blank(v))
+                .ok_or_else(|| anyhow::anyhow!("Record has no value for '{}' used in output pattern", key))?;
+            let text = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            name.extend(text.chars().map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') { c } else { '_' }
+            }));
+        }
+        rest = &after[end + 1..];
+    }
+    name.push_str(rest);
+
+    Ok(name)
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc, Duration};
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 use std::path::PathBuf;
 use sha2::{Sha256, Digest};
 
@@ -52,7 +53,11 @@ impl TemplateCache {
     pub fn set(&self, key: &str, entry: CacheEntry) -> anyhow::Result<()> {
         let path = self.cache_path(key);
         let data = bincode::serialize(&entry)?;
-        std::fs::write(path, data)?;
+        // Written aside and renamed into place, so concurrent fills never
+        // read a half-written entry
+        let mut file = tempfile::NamedTempFile::new_in(&self.cache_dir)?;
+        file.write_all(&data)?;
+        file.persist(path)?;
         Ok(())
     }
     
@@ -0,0 +1,97 @@
+use pdf_writer::Content;
+
+/// A device color parsed from `#rrggbb`, `#rgb`, `rgb(r, g, b)` (0-255) or
+/// `cmyk(c, m, y, k)` (0-100, `%` optional).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Rgb([f32; 3]),
+    Cmyk([f32; 4]),
+}
+
+impl Color {
+    pub const BLACK: Color = Color::Rgb([0.0; 3]);
+
+    pub fn parse(color: &str) -> anyhow::Result<Self> {
+        let invalid = || anyhow::anyhow!("'{}' is not a #rrggbb, rgb() or cmyk() color", color);
+        let trimmed = color.trim();
+
+        if let Some(hex) = trimmed.strip_prefix('#') {
+            let digits: Vec<u8> = hex.chars()
+                .map(|c| c.to_digit(16).map(|d| d as u8))
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?;
+            let [r, g, b] = match digits[..] {
+                [r, g, b] => [r * 17, g * 17, b * 17],
+                [r1, r2, g1, g2, b1, b2] => [r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2],
+                _ => return Err(invalid()),
+            };
+            return Ok(Color::Rgb([r, g, b].map(|c| c as f32 / 255.0)));
+        }
+
+        let lower = trimmed.to_ascii_lowercase();
+        let (components, scale) = if let Some(args) = function_args(&lower, "rgb") {
+            (args, 255.0)
+        } else if let Some(args) = function_args(&lower, "cmyk") {
+            (args, 100.0)
+        } else {
+            return Err(invalid());
+        };
+        let values: Vec<f32> = components.split(',')
+            .map(|c| c.trim().trim_end_matches('%').trim().parse::<f32>().ok())
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        let values: Vec<f32> = values.iter().map(|v| (v / scale).clamp(0.0, 1.0)).collect();
+        match values[..] {
+            [r, g, b] if scale == 255.0 => Ok(Color::Rgb([r, g, b])),
+            [c, m, y, k] if scale == 100.0 => Ok(Color::Cmyk([c, m, y, k])),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn set_fill(self, content: &mut Content) {
+        match self {
+            Color::Rgb([r, g, b]) => content.set_fill_rgb(r, g, b),
+            Color::Cmyk([c, m, y, k]) => content.set_fill_cmyk(c, m, y, k),
+        };
+    }
+
+    pub fn set_stroke(self, content: &mut Content) {
+        match self {
+            Color::Rgb([r, g, b]) => content.set_stroke_rgb(r, g, b),
+            Color::Cmyk([c, m, y, k]) => content.set_stroke_cmyk(c, m, y, k),
+        };
+    }
+}
+
+/// The text between the parentheses of `name(...)`.
+fn function_args<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    text.strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(Color::parse("#fff").unwrap(), Color::Rgb([1.0, 1.0, 1.0]));
+        assert_eq!(Color::parse(" #FF0033 ").unwrap(), Color::Rgb([1.0, 0.0, 51.0 / 255.0]));
+    }
+
+    #[test]
+    fn parses_functions() {
+        assert_eq!(Color::parse("rgb(255, 0, 51)").unwrap(), Color::Rgb([1.0, 0.0, 51.0 / 255.0]));
+        assert_eq!(Color::parse("CMYK(0, 50%, 100, 20)").unwrap(), Color::Cmyk([0.0, 0.5, 1.0, 0.2]));
+        assert_eq!(Color::parse("rgb(300, -5, 0)").unwrap(), Color::Rgb([1.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn rejects_invalid_colors() {
+        for color in ["", "red", "#12", "#12345g", "rgb(1, 2)", "cmyk(1, 2, 3)", "rgb(1, 2, 3, 4)", "rgb 1, 2, 3"] {
+            assert!(Color::parse(color).is_err(), "{} should not parse", color);
+        }
+    }
+}
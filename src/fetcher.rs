@@ -1,9 +1,7 @@
 use crate::types::{FieldData, FieldValue, ImageSource, UrlConfig};
 use reqwest::Client;
-use std::collections::HashMap;
 
-pub async fn fetch_url_with_config(config: &UrlConfig) -> anyhow::Result<Vec<u8>> {
-    let client = Client::new();
+pub async fn fetch_url_with_config(client: &Client, config: &UrlConfig) -> anyhow::Result<Vec<u8>> {
     
     let mut request = match config.method.as_deref().unwrap_or("GET") {
         "POST" => client.post(&config.url),
@@ -31,8 +29,7 @@ pub async fn fetch_url_with_config(config: &UrlConfig) -> anyhow::Result<Vec<u8>
     Ok(response.bytes().await?.to_vec())
 }
 
-pub async fn fetch_url(url: &str, config: Option<&UrlConfig>) -> anyhow::Result<Vec<u8>> {
-    let client = Client::new();
+pub async fn fetch_url(client: &Client, url: &str, config: Option<&UrlConfig>) -> anyhow::Result<Vec<u8>> {
     
     let mut request = match config.and_then(|c| c.method.as_deref()).unwrap_or("GET") {
         "POST" => client.post(url),
@@ -62,19 +59,19 @@ pub async fn fetch_url(url: &str, config: Option<&UrlConfig>) -> anyhow::Result<
     Ok(response.bytes().await?.to_vec())
 }
 
-pub async fn fetch_remote_images(fields: Vec<FieldData>) -> anyhow::Result<Vec<FieldData>> {
+pub async fn fetch_remote_images(client: &Client, fields: Vec<FieldData>) -> anyhow::Result<Vec<FieldData>> {
     let mut result = Vec::new();
     
     for mut field in fields {
-        let should_fetch = match &field.value {
-            FieldValue::Signature(ImageSource::Url(_)) | FieldValue::Image(ImageSource::Url(_)) => true,
-            _ => false,
-        };
+        let should_fetch = matches!(
+            &field.value,
+            FieldValue::Signature(ImageSource::Url(_)) | FieldValue::Image(ImageSource::Url(_))
+        );
         
         if should_fetch {
             if let FieldValue::Signature(ImageSource::Url(url_config)) | FieldValue::Image(ImageSource::Url(url_config)) = &field.value {
-                println!("  📥 Fetching image: {}", url_config.url);
-                match fetch_url(&url_config.url, Some(url_config)).await {
+                log::info!("Fetching image: {}", url_config.url);
+                match fetch_url(client, &url_config.url, Some(url_config)).await {
                     Ok(img_bytes) => {
                         let base64_img = base64::Engine::encode(
                             &base64::engine::general_purpose::STANDARD,
@@ -87,7 +84,7 @@ pub async fn fetch_remote_images(fields: Vec<FieldData>) -> anyhow::Result<Vec<F
                         };
                     }
                     Err(e) => {
-                        eprintln!("  ⚠️  Failed to fetch image for {}: {}", field.field_id, e);
+                        log::warn!("Failed to fetch image for {}: {}", field.field_id, e);
                     }
                 }
             }
@@ -99,8 +96,7 @@ pub async fn fetch_remote_images(fields: Vec<FieldData>) -> anyhow::Result<Vec<F
 }
 
 
-pub async fn fetch_with_headers(config: &UrlConfig) -> anyhow::Result<(Vec<u8>, Option<String>, Option<String>)> {
-    let client = Client::new();
+pub async fn fetch_with_headers(client: &Client, config: &UrlConfig) -> anyhow::Result<(Vec<u8>, Option<String>, Option<String>)> {
     
     let mut request = match config.method.as_deref().unwrap_or("GET") {
         "POST" => client.post(&config.url),
@@ -141,11 +137,11 @@ pub async fn fetch_with_headers(config: &UrlConfig) -> anyhow::Result<(Vec<u8>,
 }
 
 pub async fn validate_cache(
+    client: &Client,
     config: &UrlConfig,
     etag: Option<&str>,
     last_modified: Option<&str>,
 ) -> anyhow::Result<bool> {
-    let client = Client::new();
     let mut request = client.head(&config.url);
     
     if let Some(headers) = &config.headers {
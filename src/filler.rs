@@ -0,0 +1,234 @@
+use crate::acroform;
+use crate::cache::TemplateCache;
+use crate::fetcher;
+use crate::fonts::FontCache;
+use crate::merge;
+use crate::renderer::PdfFieldRenderer;
+use crate::template::PreparedTemplate;
+use crate::types::{FieldData, ProcessingMetadata, TemplateSource, TextOverflow};
+use reqwest::Client;
+use std::path::PathBuf;
+
+/// Settings shared by every fill of a run.
+#[derive(Debug, Clone)]
+pub struct FillOptions {
+    /// Flatten the result: stamp form widgets into the page content
+    pub flatten: bool,
+    /// `native` (lopdf), `python` (PyPDF2) or `bun` (pdf-lib)
+    pub merge_backend: String,
+    /// Applied to fields without their own `text_overflow`
+    pub text_overflow: TextOverflow,
+    /// Fonts named by a field's `font`. Clones share the font files read,
+    /// so each is read once however many fills use them
+    pub fonts: FontCache,
+    /// Applied to fields without their own `max_dpi`
+    pub max_dpi: Option<f32>,
+    /// Applied to fields without their own `jpeg_quality`
+    pub jpeg_quality: Option<u8>,
+}
+
+impl Default for FillOptions {
+    fn default() -> Self {
+        Self {
+            flatten: true,
+            merge_backend: "native".to_string(),
+            text_overflow: TextOverflow::default(),
+            fonts: FontCache::default(),
+            max_dpi: None,
+            jpeg_quality: None,
+        }
+    }
+}
+
+/// Render, fill form fields and merge one set of field data onto the template.
+pub async fn fill_prepared(
+    client: &Client,
+    template: &PreparedTemplate,
+    field_data: Vec<FieldData>,
+    options: &FillOptions,
+) -> anyhow::Result<(Vec<u8>, ProcessingMetadata)> {
+    let field_data = fetch_fields(client, field_data, options).await?;
+    fill_fetched(template, field_data, options)
+}
+
+/// Apply `options` to fields without their own settings and fetch remote
+/// images and signatures.
+pub(crate) async fn fetch_fields(
+    client: &Client,
+    mut field_data: Vec<FieldData>,
+    options: &FillOptions,
+) -> anyhow::Result<Vec<FieldData>> {
+    // Apply global settings to fields without explicit ones
+    for field in &mut field_data {
+        if field.text_overflow.is_none() {
+            field.text_overflow = Some(options.text_overflow.clone());
+        }
+        field.max_dpi = field.max_dpi.or(options.max_dpi);
+        field.jpeg_quality = field.jpeg_quality.or(options.jpeg_quality);
+    }
+
+    fetcher::fetch_remote_images(client, field_data).await
+}
+
+/// The blocking part of [`fill_prepared`], for fields from [`fetch_fields`]:
+/// rendering, filling form fields and merging, which may run a subprocess.
+pub(crate) fn fill_fetched(
+    template: &PreparedTemplate,
+    field_data: Vec<FieldData>,
+    options: &FillOptions,
+) -> anyhow::Result<(Vec<u8>, ProcessingMetadata)> {
+    // Fields without coordinates target the template's own form fields
+    let (field_data, form_fields): (Vec<FieldData>, Vec<FieldData>) = field_data
+        .into_iter()
+        .partition(FieldData::has_position);
+
+    // Create renderer and fill
+    let renderer = PdfFieldRenderer::new(options.fonts.clone());
+    let (filled_pdf, mut metadata) = renderer.create_populated_form(&field_data, &template.pdf_info)?;
+    let page_map = metadata.page_map();
+
+    // Form fields are filled on a copy of the parsed template; the native
+    // backend merges into it, the others take it saved back to bytes
+    let final_pdf = if options.merge_backend == "native" {
+        let mut document = template.document.clone();
+        if !form_fields.is_empty() {
+            acroform::fill_form_document(&mut document, &form_fields, options.flatten, &mut metadata)?;
+        }
+        merge::merge_into_document(document, &filled_pdf, &page_map, options.flatten)?
+    } else if form_fields.is_empty() {
+        merge::merge_pdfs_bytes(&template.bytes, &filled_pdf, &page_map, options.flatten, &options.merge_backend)?
+    } else {
+        let mut document = template.document.clone();
+        acroform::fill_form_document(&mut document, &form_fields, options.flatten, &mut metadata)?;
+        let mut template_bytes = Vec::new();
+        document.save_to(&mut template_bytes)?;
+        merge::merge_pdfs_bytes(&template_bytes, &filled_pdf, &page_map, options.flatten, &options.merge_backend)?
+    };
+
+    // Continuation sheets are the overlay pages past the stamped ones
+    let final_pdf = if metadata.continuations.is_empty() {
+        final_pdf
+    } else {
+        merge::append_overlay_pages(&final_pdf, &filled_pdf, page_map.len())?
+    };
+
+    Ok((final_pdf, metadata))
+}
+
+/// Builder for filling one template.
+///
+/// Defaults: flatten, `native` merge backend, `overflow` text, no cache. The
+/// `python` and `bun` backends expect their runtime to be installed already;
+/// see [`merge::check_dependencies`].
+#[derive(Debug, Clone)]
+pub struct Filler {
+    template: String,
+    fields: Vec<FieldData>,
+    options: FillOptions,
+    cache: Option<(Option<PathBuf>, Option<i64>)>,
+    cache_refresh: bool,
+    client: Option<Client>,
+}
+
+impl Filler {
+    /// `template` is a path, an http(s) URL or a JSON `TemplateSource`.
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            fields: Vec::new(),
+            options: FillOptions::default(),
+            cache: None,
+            cache_refresh: false,
+            client: None,
+        }
+    }
+
+    pub fn from_source(source: &TemplateSource) -> anyhow::Result<Self> {
+        let template = match source {
+            TemplateSource::Path(path) => path.clone(),
+            TemplateSource::Url(config) => serde_json::to_string(config)?,
+        };
+        Ok(Self::new(template))
+    }
+
+    pub fn fields(mut self, fields: Vec<FieldData>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn field(mut self, field: FieldData) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn text_overflow(mut self, text_overflow: TextOverflow) -> Self {
+        self.options.text_overflow = text_overflow;
+        self
+    }
+
+    pub fn flatten(mut self, flatten: bool) -> Self {
+        self.options.flatten = flatten;
+        self
+    }
+
+    pub fn merge_backend(mut self, merge_backend: impl Into<String>) -> Self {
+        self.options.merge_backend = merge_backend.into();
+        self
+    }
+
+    pub fn font_dir(mut self, font_dir: impl Into<PathBuf>) -> Self {
+        self.options.fonts = FontCache::new(Some(font_dir.into()));
+        self
+    }
+
+    /// Downsample images to this resolution at their placed size.
+    pub fn max_dpi(mut self, max_dpi: f32) -> Self {
+        self.options.max_dpi = Some(max_dpi);
+        self
+    }
+
+    /// Re-encode images as JPEG at this quality (1-100) when smaller.
+    pub fn jpeg_quality(mut self, jpeg_quality: u8) -> Self {
+        self.options.jpeg_quality = Some(jpeg_quality);
+        self
+    }
+
+    /// Cache remote templates in `cache_dir` (default `~/.fill-pdf/cache`)
+    /// for `ttl_seconds` (default 3600).
+    pub fn cache(mut self, cache_dir: Option<PathBuf>, ttl_seconds: Option<i64>) -> Self {
+        self.cache = Some((cache_dir, ttl_seconds));
+        self
+    }
+
+    pub fn cache_refresh(mut self, cache_refresh: bool) -> Self {
+        self.cache_refresh = cache_refresh;
+        self
+    }
+
+    /// Reuse an existing HTTP client for the template and remote images.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Load the template and return the filled PDF with its metadata.
+    pub async fn fill(&self) -> anyhow::Result<(Vec<u8>, ProcessingMetadata)> {
+        let client = self.client.clone().unwrap_or_default();
+        let cache = match &self.cache {
+            Some((dir, ttl)) => Some(TemplateCache::new(dir.clone(), *ttl)?),
+            None => None,
+        };
+
+        let template = PreparedTemplate::load(&client, &self.template, cache.as_ref(), self.cache_refresh).await?;
+        fill_prepared(&client, &template, self.fields.clone(), &self.options).await
+    }
+
+    /// [`Filler::fill`] on a private runtime. Must not be called from inside
+    /// a tokio runtime.
+    pub fn fill_blocking(&self) -> anyhow::Result<(Vec<u8>, ProcessingMetadata)> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(self.fill())
+    }
+}
@@ -0,0 +1,529 @@
+use pdf_writer::types::{CidFontType, FontFlags, SystemInfo, UnicodeCmap};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str};
+use rustybuzz::ttf_parser;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use subsetter::GlyphRemapper;
+
+const FONT_EXTENSIONS: [&str; 4] = ["ttf", "otf", "ttc", "otc"];
+
+/// Font used to draw a piece of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontKey {
+    /// Base-14 Helvetica (WinAnsi encoded, no embedding)
+    Helvetica,
+    /// TrueType/OpenType font embedded as a CID font, by registry index
+    Embedded(usize),
+}
+
+/// One TJ array: vertical offset (1/1000 em) and `(glyph bytes, adjustment)` items.
+type GlyphRun = (f32, Vec<(Vec<u8>, f32)>);
+
+/// A glyph after shaping. All values are in 1/1000 em.
+struct ShapedGlyph {
+    cid: u16,
+    /// Width the PDF viewer advances by (the `/W` entry for this glyph)
+    nominal: f32,
+    /// Advance requested by the shaper (includes kerning)
+    advance: f32,
+    x_offset: f32,
+    y_offset: f32,
+    /// Glyph of a space character, widened by word spacing
+    space: bool,
+}
+
+pub struct EmbeddedFont {
+    resource_name: String,
+    font_ref: Ref,
+    path: PathBuf,
+    data: Arc<Vec<u8>>,
+    index: u32,
+    units_per_em: f32,
+    /// Original glyph ids remapped to consecutive CIDs for subsetting
+    remapper: GlyphRemapper,
+    /// Unicode text per CID, for the ToUnicode CMap
+    cid_text: BTreeMap<u16, String>,
+    /// Advance width per CID, in 1/1000 em
+    cid_widths: BTreeMap<u16, f32>,
+    /// Characters without a glyph, warned about once each
+    missing: BTreeSet<char>,
+}
+
+impl EmbeddedFont {
+    /// Load face `index` of the font file; only collections have more than one.
+    fn load(path: &Path, data: Arc<Vec<u8>>, index: u32, resource_name: String, font_ref: Ref) -> anyhow::Result<Self> {
+        let faces = ttf_parser::fonts_in_collection(&data).unwrap_or(1);
+        if index >= faces {
+            anyhow::bail!("Font {} has {} face(s), no face {}", path.display(), faces, index);
+        }
+        let face = ttf_parser::Face::parse(&data, index)
+            .map_err(|e| anyhow::anyhow!("Failed to parse font {}: {}", path.display(), e))?;
+        let units_per_em = face.units_per_em() as f32;
+
+        Ok(Self {
+            resource_name,
+            font_ref,
+            path: path.to_path_buf(),
+            data,
+            index,
+            units_per_em,
+            remapper: GlyphRemapper::new(),
+            cid_text: BTreeMap::new(),
+            cid_widths: BTreeMap::new(),
+            missing: BTreeSet::new(),
+        })
+    }
+
+    /// Advance width of `text` after shaping (including kerning), in 1/1000 em.
+    fn advance(&self, text: &str) -> f32 {
+        let Some(face) = rustybuzz::Face::from_slice(&self.data, self.index) else {
+            return 0.0;
+        };
+
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        let output = rustybuzz::shape(&face, &[], buffer);
+
+        let units: i32 = output.glyph_positions().iter().map(|p| p.x_advance).sum();
+        units as f32 * 1000.0 / self.units_per_em
+    }
+
+    /// Shape `text` and register every glyph it uses for embedding.
+    fn shape(&mut self, text: &str) -> Vec<ShapedGlyph> {
+        let Some(face) = rustybuzz::Face::from_slice(&self.data, self.index) else {
+            return Vec::new();
+        };
+
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        let output = rustybuzz::shape(&face, &[], buffer);
+
+        let scale = 1000.0 / self.units_per_em;
+
+        // Cluster values are byte offsets into `text`; each cluster's text spans
+        // up to the next larger cluster start (glyph order is visual for RTL)
+        let mut cluster_starts: Vec<usize> = output.glyph_infos().iter().map(|g| g.cluster as usize).collect();
+        cluster_starts.sort_unstable();
+        cluster_starts.dedup();
+
+        let mut seen_clusters = Vec::new();
+        let mut glyphs = Vec::with_capacity(output.len());
+
+        for (info, pos) in output.glyph_infos().iter().zip(output.glyph_positions()) {
+            let gid = info.glyph_id as u16;
+            let cid = self.remapper.remap(gid);
+
+            let nominal = face.glyph_hor_advance(ttf_parser::GlyphId(gid)).unwrap_or(0) as f32 * scale;
+            self.cid_widths.insert(cid, nominal);
+
+            let cluster = info.cluster as usize;
+            let space = text.as_bytes().get(cluster) == Some(&b' ');
+            if !seen_clusters.contains(&cluster) {
+                seen_clusters.push(cluster);
+                let end = cluster_starts.iter()
+                    .copied()
+                    .find(|&start| start > cluster)
+                    .unwrap_or(text.len());
+                if let Some(cluster_text) = text.get(cluster..end) {
+                    self.cid_text.entry(cid).or_insert_with(|| cluster_text.to_string());
+                    // Glyph 0 is .notdef, usually drawn as an empty box
+                    if gid == 0 {
+                        for c in cluster_text.chars().filter(|c| !c.is_control()) {
+                            if self.missing.insert(c) {
+                                log::warn!(
+                                    "Font {} has no glyph for '{}' (U+{:04X}); it is drawn as .notdef",
+                                    self.path.display(), c, c as u32
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+
+            glyphs.push(ShapedGlyph {
+                cid,
+                nominal,
+                advance: pos.x_advance as f32 * scale,
+                x_offset: pos.x_offset as f32 * scale,
+                y_offset: pos.y_offset as f32 * scale,
+                space,
+            });
+        }
+
+        glyphs
+    }
+
+    /// Write the Type0 font, its CID font, descriptor, subset font file and
+    /// ToUnicode CMap.
+    fn write(&self, pdf: &mut Pdf, next_ref: &mut i32) -> anyhow::Result<()> {
+        let face = ttf_parser::Face::parse(&self.data, self.index)
+            .map_err(|e| anyhow::anyhow!("Failed to parse font {}: {}", self.path.display(), e))?;
+
+        let cid_ref = alloc_ref(next_ref);
+        let descriptor_ref = alloc_ref(next_ref);
+        let file_ref = alloc_ref(next_ref);
+        let cmap_ref = alloc_ref(next_ref);
+
+        let is_cff = face.tables().cff.is_some();
+        let base_font = format!("{}+{}", self.subset_tag(), postscript_name(&face, &self.path));
+        let base_font = Name(base_font.as_bytes());
+
+        let system_info = SystemInfo {
+            registry: Str(b"Adobe"),
+            ordering: Str(b"Identity"),
+            supplement: 0,
+        };
+
+        pdf.type0_font(self.font_ref)
+            .base_font(base_font)
+            .encoding_predefined(Name(b"Identity-H"))
+            .descendant_font(cid_ref)
+            .to_unicode(cmap_ref);
+
+        let mut cid = pdf.cid_font(cid_ref);
+        cid.subtype(if is_cff { CidFontType::Type0 } else { CidFontType::Type2 })
+            .base_font(base_font)
+            .system_info(system_info)
+            .font_descriptor(descriptor_ref)
+            .default_width(0.0);
+        if !is_cff {
+            cid.cid_to_gid_map_predefined(Name(b"Identity"));
+        }
+        {
+            let mut widths = cid.widths();
+            for (&glyph, &width) in &self.cid_widths {
+                widths.consecutive(glyph, [width]);
+            }
+        }
+        cid.finish();
+
+        let scale = 1000.0 / self.units_per_em;
+        let bbox = face.global_bounding_box();
+        let ascent = face.ascender() as f32 * scale;
+        let descent = face.descender() as f32 * scale;
+        let cap_height = face.capital_height().map(|h| h as f32 * scale).unwrap_or(ascent);
+
+        let mut flags = FontFlags::SYMBOLIC;
+        if face.is_italic() {
+            flags |= FontFlags::ITALIC;
+        }
+        if face.is_monospaced() {
+            flags |= FontFlags::FIXED_PITCH;
+        }
+
+        let mut descriptor = pdf.font_descriptor(descriptor_ref);
+        descriptor.name(base_font)
+            .flags(flags)
+            .bbox(Rect::new(
+                bbox.x_min as f32 * scale,
+                bbox.y_min as f32 * scale,
+                bbox.x_max as f32 * scale,
+                bbox.y_max as f32 * scale,
+            ))
+            .italic_angle(face.italic_angle())
+            .ascent(ascent)
+            .descent(descent)
+            .cap_height(cap_height)
+            // No reliable source for stem width in OpenType; derive from weight like most writers
+            .stem_v(10.0 + 0.244 * (face.weight().to_number() as f32 - 50.0));
+        if is_cff {
+            descriptor.font_file3(file_ref);
+        } else {
+            descriptor.font_file2(file_ref);
+        }
+        descriptor.finish();
+
+        let subset = subsetter::subset(&self.data, self.index, &self.remapper)
+            .map_err(|e| anyhow::anyhow!("Failed to subset font {}: {}", self.path.display(), e))?;
+        let compressed = deflate(&subset)?;
+        let mut file = pdf.stream(file_ref, &compressed);
+        file.filter(Filter::FlateDecode);
+        if is_cff {
+            file.pair(Name(b"Subtype"), Name(b"OpenType"));
+        } else {
+            file.pair(Name(b"Length1"), subset.len() as i32);
+        }
+        file.finish();
+
+        let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+        for (&glyph, text) in &self.cid_text {
+            cmap.pair_with_multiple(glyph, text.chars());
+        }
+        pdf.cmap(cmap_ref, &cmap.finish());
+
+        Ok(())
+    }
+
+    /// Six uppercase letters derived from the glyph set, as required for subset fonts.
+    fn subset_tag(&self) -> String {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.path.hash(&mut hasher);
+        self.index.hash(&mut hasher);
+        for gid in self.remapper.remapped_gids() {
+            gid.hash(&mut hasher);
+        }
+        let mut value = Hasher::finish(&hasher);
+        (0..6).map(|_| {
+            let c = (b'A' + (value % 26) as u8) as char;
+            value /= 26;
+            c
+        }).collect()
+    }
+}
+
+/// Path and contents of a font file.
+type FontFile = (PathBuf, Arc<Vec<u8>>);
+
+/// Font files found by name in a font directory, or at explicit paths. Each
+/// file is read once; clones share the files read so far, so one cache serves
+/// every document of a batch or server.
+#[derive(Clone, Default)]
+pub struct FontCache {
+    font_dir: Option<PathBuf>,
+    /// Each font file, by the `font` name that found it
+    files: Arc<Mutex<HashMap<String, FontFile>>>,
+}
+
+impl FontCache {
+    pub fn new(font_dir: Option<PathBuf>) -> Self {
+        Self {
+            font_dir,
+            files: Arc::default(),
+        }
+    }
+
+    /// Path and contents of the font file `name` refers to, read on first use.
+    fn file(&self, name: &str) -> anyhow::Result<FontFile> {
+        let mut files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(file) = files.get(name) {
+            return Ok(file.clone());
+        }
+
+        let path = self.find_font_file(name)
+            .ok_or_else(|| anyhow::anyhow!("Font '{}' not found", name))?;
+        let data = std::fs::read(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read font {}: {}", path.display(), e))?;
+        let file = (path, Arc::new(data));
+        files.insert(name.to_string(), file.clone());
+        Ok(file)
+    }
+
+    fn find_font_file(&self, name: &str) -> Option<PathBuf> {
+        let direct = PathBuf::from(name);
+        if direct.is_file() {
+            return Some(direct);
+        }
+
+        let dir = self.font_dir.as_ref()?;
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+
+        // Match the file stem case-insensitively against known font extensions
+        let wanted = name.to_lowercase();
+        let entries = std::fs::read_dir(dir).ok()?;
+        let mut matches: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                let ext_ok = p.extension()
+                    .and_then(|e| e.to_str())
+                    .map(|e| FONT_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+                    .unwrap_or(false);
+                let stem_ok = p.file_stem()
+                    .and_then(|s| s.to_str())
+                    .map(|s| s.to_lowercase() == wanted)
+                    .unwrap_or(false);
+                ext_ok && stem_ok
+            })
+            .collect();
+        matches.sort();
+        matches.into_iter().next()
+    }
+}
+
+impl std::fmt::Debug for FontCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FontCache").field("font_dir", &self.font_dir).finish_non_exhaustive()
+    }
+}
+
+/// Fonts embedded in the current document, read through a [`FontCache`].
+pub struct FontRegistry {
+    cache: FontCache,
+    fonts: Vec<EmbeddedFont>,
+    by_name: HashMap<String, usize>,
+}
+
+impl FontRegistry {
+    pub fn new(cache: FontCache) -> Self {
+        Self {
+            cache,
+            fonts: Vec::new(),
+            by_name: HashMap::new(),
+        }
+    }
+
+    /// Resolve a field's `font` value, loading the font on first use.
+    ///
+    /// `name` may be a path to a font file or a file name (with or without
+    /// extension) inside the font directory, followed by `#N` to pick face
+    /// `N` of a font collection (`.ttc`/`.otc`); the first face otherwise.
+    pub fn resolve(&mut self, name: &str, next_ref: &mut i32) -> anyhow::Result<FontKey> {
+        if let Some(&index) = self.by_name.get(name) {
+            return Ok(FontKey::Embedded(index));
+        }
+
+        let (file_name, face_index) = match name.rsplit_once('#') {
+            Some((file_name, face)) if !face.is_empty() && face.bytes().all(|b| b.is_ascii_digit()) => {
+                (file_name, face.parse()?)
+            }
+            _ => (name, 0),
+        };
+        let (path, data) = self.cache.file(file_name)?;
+        let index = self.fonts.len();
+        let resource_name = format!("EF{}", index + 1);
+        let font = EmbeddedFont::load(&path, data, face_index, resource_name, alloc_ref(next_ref))?;
+
+        self.fonts.push(font);
+        self.by_name.insert(name.to_string(), index);
+        Ok(FontKey::Embedded(index))
+    }
+
+    /// Resource name/ref pairs for every embedded font loaded so far.
+    pub fn resources(&self) -> Vec<(String, Ref)> {
+        self.fonts.iter().map(|f| (f.resource_name.clone(), f.font_ref)).collect()
+    }
+
+    /// Width of `text` in the embedded font at `font_size`, in points.
+    pub fn text_width(&self, index: usize, font_size: f32, text: &str) -> f32 {
+        self.fonts[index].advance(text) / 1000.0 * font_size
+    }
+
+    /// Select the font and show `text` with an embedded font, honoring the
+    /// shaper's kerning and mark offsets. `word_spacing` points are added
+    /// after each space here, as `Tw` skips two-byte codes.
+    pub fn show_text(&mut self, content: &mut Content, index: usize, font_size: f32, text: &str, word_spacing: f32) {
+        let font = &mut self.fonts[index];
+        let glyphs = font.shape(text);
+
+        content.set_font(Name(font.resource_name.as_bytes()), font_size);
+
+        // Split into runs sharing the same vertical offset; each run becomes one
+        // TJ array and offsets are applied with the text rise operator
+        let mut runs: Vec<GlyphRun> = Vec::new();
+        let mut pen = 0.0;
+        let mut pdf_pos = 0.0;
+
+        for glyph in &glyphs {
+            let target = pen + glyph.x_offset;
+            // TJ adjustments are subtracted from the position
+            let adjust = pdf_pos - target;
+
+            if runs.last().map(|(rise, _)| *rise != glyph.y_offset).unwrap_or(true) {
+                runs.push((glyph.y_offset, Vec::new()));
+            }
+            let (_, items) = runs.last_mut().unwrap();
+            items.push((glyph.cid.to_be_bytes().to_vec(), adjust));
+
+            pdf_pos = target + glyph.nominal;
+            pen += glyph.advance;
+            if glyph.space {
+                pen += word_spacing * 1000.0 / font_size;
+            }
+        }
+
+        let mut rise = 0.0;
+        for (run_rise, items) in runs {
+            if run_rise != rise {
+                content.set_rise(run_rise / 1000.0 * font_size);
+                rise = run_rise;
+            }
+
+            let mut positioned = content.show_positioned();
+            let mut array = positioned.items();
+            for (bytes, adjust) in &items {
+                if adjust.abs() > 0.01 {
+                    array.adjust(*adjust);
+                }
+                array.show(Str(bytes));
+            }
+        }
+        if rise != 0.0 {
+            content.set_rise(0.0);
+        }
+    }
+
+    /// Write all embedded fonts into the PDF.
+    pub fn finish(self, pdf: &mut Pdf, next_ref: &mut i32) -> anyhow::Result<()> {
+        for font in &self.fonts {
+            font.write(pdf, next_ref)?;
+        }
+        Ok(())
+    }
+}
+
+/// Encode text for a base-14 font using WinAnsiEncoding. Characters outside
+/// the encoding become `?`; the flag reports whether that happened.
+pub fn encode_win_ansi(text: &str) -> (Vec<u8>, bool) {
+    let mut lossless = true;
+    let bytes = text.chars().map(|c| {
+        win_ansi_byte(c).unwrap_or_else(|| {
+            lossless = false;
+            b'?'
+        })
+    }).collect();
+    (bytes, lossless)
+}
+
+fn win_ansi_byte(c: char) -> Option<u8> {
+    let code = c as u32;
+    match code {
+        0x20..=0x7E | 0xA0..=0xFF => Some(code as u8),
+        _ => {
+            // Windows-1252 assignments in the 0x80-0x9F range
+            let byte = match c {
+                '€' => 0x80, '‚' => 0x82, 'ƒ' => 0x83, '„' => 0x84, '…' => 0x85,
+                '†' => 0x86, '‡' => 0x87, 'ˆ' => 0x88, '‰' => 0x89, 'Š' => 0x8A,
+                '‹' => 0x8B, 'Œ' => 0x8C, 'Ž' => 0x8E, '‘' => 0x91, '’' => 0x92,
+                '“' => 0x93, '”' => 0x94, '•' => 0x95, '–' => 0x96, '—' => 0x97,
+                '˜' => 0x98, '™' => 0x99, 'š' => 0x9A, '›' => 0x9B, 'œ' => 0x9C,
+                'ž' => 0x9E, 'Ÿ' => 0x9F,
+                _ => return None,
+            };
+            Some(byte)
+        }
+    }
+}
+
+fn postscript_name(face: &ttf_parser::Face, path: &Path) -> String {
+    let from_table = face.names()
+        .into_iter()
+        .filter(|n| n.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+        .find_map(|n| n.to_string());
+
+    let name = from_table.unwrap_or_else(|| {
+        path.file_stem().and_then(|s| s.to_str()).unwrap_or("Font").to_string()
+    });
+
+    name.chars()
+        .filter(|c| c.is_ascii_graphic() && !"[](){}<>/%#".contains(*c))
+        .collect()
+}
+
+pub(crate) fn deflate(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn alloc_ref(next_ref: &mut i32) -> Ref {
+    let id = Ref::new(*next_ref);
+    *next_ref += 1;
+    id
+}
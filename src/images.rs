@@ -0,0 +1,303 @@
+use crate::fonts::deflate;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, GenericImageView, ImageDecoder};
+use pdf_writer::{Filter, Finish, Pdf, Ref};
+
+/// Used when an image is re-encoded without an explicit `jpeg_quality`.
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// Pixel size of an image file, read from its header where possible.
+pub fn dimensions(data: &[u8]) -> anyhow::Result<(u32, u32)> {
+    if let Some(jpeg) = JpegInfo::parse(data) {
+        return Ok((jpeg.width, jpeg.height));
+    }
+    Ok(image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()?
+        .into_dimensions()?)
+}
+
+/// EXIF orientation of an image file; upright when it has none.
+pub fn orientation(data: &[u8]) -> Orientation {
+    image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format().ok()
+        .and_then(|reader| reader.into_decoder().ok())
+        .and_then(|mut decoder| decoder.orientation().ok())
+        .unwrap_or(Orientation::NoTransforms)
+}
+
+/// Linear part of the transform that displays stored pixels upright, for an
+/// image centered on the origin (PDF axes, y up).
+pub fn orientation_matrix(orientation: Orientation) -> [f32; 4] {
+    match orientation {
+        Orientation::NoTransforms => [1.0, 0.0, 0.0, 1.0],
+        Orientation::Rotate90 => [0.0, -1.0, 1.0, 0.0],
+        Orientation::Rotate180 => [-1.0, 0.0, 0.0, -1.0],
+        Orientation::Rotate270 => [0.0, 1.0, -1.0, 0.0],
+        Orientation::FlipHorizontal => [-1.0, 0.0, 0.0, 1.0],
+        Orientation::FlipVertical => [1.0, 0.0, 0.0, -1.0],
+        Orientation::Rotate90FlipH => [0.0, -1.0, -1.0, 0.0],
+        Orientation::Rotate270FlipH => [0.0, 1.0, 1.0, 0.0],
+    }
+}
+
+/// Whether the orientation turns the image on its side.
+pub fn swaps_axes(orientation: Orientation) -> bool {
+    matches!(
+        orientation,
+        Orientation::Rotate90 | Orientation::Rotate270 | Orientation::Rotate90FlipH | Orientation::Rotate270FlipH
+    )
+}
+
+/// Color space of an image stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageColor {
+    Gray,
+    Rgb,
+    Cmyk,
+}
+
+/// An image ready to be written as an image XObject.
+pub struct EncodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub color: ImageColor,
+    pub filter: Filter,
+    pub data: Vec<u8>,
+    /// Adobe CMYK JPEGs store inverted components
+    pub inverted: bool,
+    /// Flate-compressed 8-bit alpha channel, written as the image's `/SMask`
+    pub alpha: Option<Vec<u8>>,
+}
+
+impl EncodedImage {
+    /// Encode image file bytes. Baseline and progressive 8-bit JPEGs pass
+    /// through unchanged (DCTDecode); everything else is decoded and
+    /// Flate-compressed, keeping any non-opaque alpha channel.
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        if let Some(jpeg) = JpegInfo::parse(data) {
+            if jpeg.huffman_dct && jpeg.precision == 8 {
+                if let Some(color) = jpeg.color() {
+                    return Ok(Self {
+                        width: jpeg.width,
+                        height: jpeg.height,
+                        color,
+                        filter: Filter::DctDecode,
+                        data: data.to_vec(),
+                        inverted: color == ImageColor::Cmyk && jpeg.adobe,
+                        alpha: None,
+                    });
+                }
+            }
+        }
+
+        Self::from_image(&image::load_from_memory(data)?)
+    }
+
+    /// Encode image file bytes, downsampling to at most `max_size` pixels.
+    ///
+    /// Resampled images, and any image when `jpeg_quality` is set, are
+    /// re-encoded: as JPEG when opaque, otherwise Flate with a soft mask. A
+    /// quality-only re-encode is kept only if it is smaller than the original.
+    pub fn encode(data: &[u8], max_size: Option<(u32, u32)>, jpeg_quality: Option<u8>) -> anyhow::Result<Self> {
+        let (width, height) = dimensions(data)?;
+        if width == 0 || height == 0 {
+            anyhow::bail!("Image is {}x{} pixels, nothing to draw", width, height);
+        }
+        let target = max_size
+            .map(|(w, h)| (w.clamp(1, width), h.clamp(1, height)))
+            .filter(|&(w, h)| w < width || h < height);
+
+        if target.is_none() && jpeg_quality.is_none() {
+            return Self::from_bytes(data);
+        }
+
+        let mut img = image::load_from_memory(data)?;
+        if let Some((w, h)) = target {
+            img = img.resize_exact(w, h, FilterType::Lanczos3);
+        }
+        let quality = jpeg_quality.unwrap_or(DEFAULT_JPEG_QUALITY).clamp(1, 100);
+        let encoded = Self::from_image_lossy(&img, quality)?;
+
+        if target.is_none() {
+            let original = Self::from_bytes(data)?;
+            if original.byte_size() <= encoded.byte_size() {
+                return Ok(original);
+            }
+        }
+        Ok(encoded)
+    }
+
+    /// JPEG for opaque images, Flate plus soft mask for transparent ones.
+    fn from_image_lossy(img: &DynamicImage, quality: u8) -> anyhow::Result<Self> {
+        if img.color().has_alpha() && img.to_rgba8().pixels().any(|p| p.0[3] != u8::MAX) {
+            return Self::from_image(img);
+        }
+
+        let (width, height) = img.dimensions();
+        let mut data = Vec::new();
+        let color = if img.color().has_color() {
+            JpegEncoder::new_with_quality(&mut data, quality).encode_image(&img.to_rgb8())?;
+            ImageColor::Rgb
+        } else {
+            JpegEncoder::new_with_quality(&mut data, quality).encode_image(&img.to_luma8())?;
+            ImageColor::Gray
+        };
+
+        Ok(Self {
+            width,
+            height,
+            color,
+            filter: Filter::DctDecode,
+            data,
+            inverted: false,
+            alpha: None,
+        })
+    }
+
+    /// Embedded size: image stream plus soft mask.
+    pub fn byte_size(&self) -> usize {
+        self.data.len() + self.alpha.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Flate-encode decoded pixels.
+    pub fn from_image(img: &DynamicImage) -> anyhow::Result<Self> {
+        let (width, height) = img.dimensions();
+        let color_type = img.color();
+
+        let (color, pixels) = if color_type.has_color() {
+            (ImageColor::Rgb, img.to_rgb8().into_raw())
+        } else {
+            (ImageColor::Gray, img.to_luma8().into_raw())
+        };
+
+        let alpha = if color_type.has_alpha() {
+            let alpha: Vec<u8> = img.to_rgba8().pixels().map(|p| p.0[3]).collect();
+            if alpha.iter().all(|&a| a == u8::MAX) {
+                None
+            } else {
+                Some(deflate(&alpha)?)
+            }
+        } else {
+            None
+        };
+
+        Ok(Self {
+            width,
+            height,
+            color,
+            filter: Filter::FlateDecode,
+            data: deflate(&pixels)?,
+            inverted: false,
+            alpha,
+        })
+    }
+
+    /// Write the image (and its soft mask) at `image_id`.
+    pub fn write(&self, pdf: &mut Pdf, image_id: Ref, next_ref: &mut i32) {
+        let mask_id = self.alpha.as_ref().map(|alpha| {
+            let mask_id = Ref::new(*next_ref);
+            *next_ref += 1;
+            let mut mask = pdf.image_xobject(mask_id, alpha);
+            mask.filter(Filter::FlateDecode);
+            mask.width(self.width as i32);
+            mask.height(self.height as i32);
+            mask.color_space().device_gray();
+            mask.bits_per_component(8);
+            mask.finish();
+            mask_id
+        });
+
+        let mut image = pdf.image_xobject(image_id, &self.data);
+        image.filter(self.filter);
+        image.width(self.width as i32);
+        image.height(self.height as i32);
+        match self.color {
+            ImageColor::Gray => image.color_space().device_gray(),
+            ImageColor::Rgb => image.color_space().device_rgb(),
+            ImageColor::Cmyk => image.color_space().device_cmyk(),
+        };
+        image.bits_per_component(8);
+        if self.inverted {
+            image.decode([1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+        }
+        if let Some(mask_id) = mask_id {
+            image.s_mask(mask_id);
+        }
+        image.finish();
+    }
+}
+
+/// Frame header of a JPEG file, read without decoding the scan data.
+struct JpegInfo {
+    width: u32,
+    height: u32,
+    /// Baseline, extended or progressive Huffman DCT (what DCTDecode supports)
+    huffman_dct: bool,
+    precision: u8,
+    components: u8,
+    /// Has an Adobe APP14 segment
+    adobe: bool,
+}
+
+impl JpegInfo {
+    fn parse(data: &[u8]) -> Option<Self> {
+        if !data.starts_with(&[0xFF, 0xD8]) {
+            return None;
+        }
+
+        let mut adobe = false;
+        let mut pos = 2;
+        while pos + 4 <= data.len() {
+            if data[pos] != 0xFF {
+                return None;
+            }
+            let marker = data[pos + 1];
+            // Fill bytes and standalone markers carry no length
+            if marker == 0xFF {
+                pos += 1;
+                continue;
+            }
+            if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+                pos += 2;
+                continue;
+            }
+
+            let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+            let segment = data.get(pos + 4..pos + 2 + length)?;
+
+            match marker {
+                0xEE if segment.starts_with(b"Adobe") => adobe = true,
+                // SOF0-SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+                0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                    if segment.len() < 6 {
+                        return None;
+                    }
+                    return Some(Self {
+                        huffman_dct: marker <= 0xC2,
+                        precision: segment[0],
+                        height: u16::from_be_bytes([segment[1], segment[2]]) as u32,
+                        width: u16::from_be_bytes([segment[3], segment[4]]) as u32,
+                        components: segment[5],
+                        adobe,
+                    });
+                }
+                // Start of scan before any frame header
+                0xDA => return None,
+                _ => {}
+            }
+            pos += 2 + length;
+        }
+        None
+    }
+
+    fn color(&self) -> Option<ImageColor> {
+        match self.components {
+            1 => Some(ImageColor::Gray),
+            3 => Some(ImageColor::Rgb),
+            4 => Some(ImageColor::Cmyk),
+            _ => None,
+        }
+    }
+}
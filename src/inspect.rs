@@ -0,0 +1,312 @@
+use crate::acroform::{self, FieldKind, FormField};
+use crate::merge::{resolve_array, resolve_dict};
+use crate::types;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Field flags shared by all field types (PDF 32000 table 221)
+const FF_READ_ONLY: i64 = 1;
+const FF_REQUIRED: i64 = 1 << 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateInfo {
+    pub page_count: usize,
+    pub pages: Vec<PageInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    /// Zero-based, as used by `FieldData.page`
+    pub page_number: u32,
+    pub width: f32,
+    pub height: f32,
+    pub media_box: [f32; 4],
+    pub crop_box: Option<[f32; 4]>,
+    pub rotate: i64,
+    pub fonts: Vec<FontInfo>,
+    /// Annotation count per subtype (`Widget`, `Link`, ...)
+    pub annotations: BTreeMap<String, usize>,
+    pub fields: Vec<FormFieldInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontInfo {
+    pub base_font: String,
+    pub subtype: String,
+    pub embedded: bool,
+}
+
+/// One widget of a template form field. Fields with several widgets (radio
+/// groups, repeated fields) appear once per widget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormFieldInfo {
+    /// Fully qualified name, usable as `field_id` without coordinates
+    pub name: String,
+    /// `field_type` to fill it with
+    pub field_type: String,
+    /// Widget rectangle in PDF coordinates (origin bottom-left)
+    pub rect: [f32; 4],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    /// Appearance state a checkbox or radio widget uses for "on"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<i64>,
+    pub read_only: bool,
+    pub required: bool,
+}
+
+/// Describe the pages, fonts, annotations and form fields of a template.
+pub fn inspect_template(doc: &Document) -> anyhow::Result<TemplateInfo> {
+    let pdf_info = types::extract_pdf_info(doc)?;
+    let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+
+    let form_fields = acroform::collect_fields(doc);
+    let mut widgets: HashMap<ObjectId, (&str, &FormField)> = HashMap::new();
+    for (name, field) in &form_fields {
+        for &widget in &field.widgets {
+            widgets.insert(widget, (name.as_str(), field));
+        }
+    }
+
+    let mut pages = Vec::with_capacity(page_ids.len());
+    let mut placed = HashSet::new();
+
+    for (page_info, &page_id) in pdf_info.pages.iter().zip(&page_ids) {
+        let page = doc.get_dictionary(page_id)?;
+
+        let annots = page.get(b"Annots").ok()
+            .and_then(|a| resolve_array(doc, a))
+            .cloned()
+            .unwrap_or_default();
+
+        let mut annotations = BTreeMap::new();
+        let mut fields = Vec::new();
+        for annot in &annots {
+            let Some(dict) = resolve_dict(doc, annot) else {
+                continue;
+            };
+            let subtype = dict.get(b"Subtype").and_then(Object::as_name)
+                .map(|s| String::from_utf8_lossy(s).into_owned())
+                .unwrap_or_else(|_| "Unknown".to_string());
+            *annotations.entry(subtype).or_insert(0) += 1;
+
+            let Ok(annot_id) = annot.as_reference() else {
+                continue;
+            };
+            if let Some(info) = widgets.get(&annot_id).and_then(|&(name, field)| field_info(doc, name, field, annot_id)) {
+                fields.push(info);
+                placed.insert(annot_id);
+            }
+        }
+
+        pages.push(PageInfo {
+            page_number: page_info.page_number,
+            width: page_info.width,
+            height: page_info.height,
+            media_box: types::page_box(doc, page, b"MediaBox")
+                .unwrap_or([0.0, 0.0, page_info.width, page_info.height]),
+            crop_box: types::page_box(doc, page, b"CropBox"),
+            rotate: page_rotate(doc, page),
+            fonts: page_fonts(doc, page),
+            annotations,
+            fields,
+        });
+    }
+
+    // Widgets missing from every /Annots array can still name their page with /P
+    for (name, field) in &form_fields {
+        for &widget in &field.widgets {
+            if placed.contains(&widget) {
+                continue;
+            }
+            let page_index = doc.get_dictionary(widget).ok()
+                .and_then(|w| w.get(b"P").and_then(Object::as_reference).ok())
+                .and_then(|p| page_ids.iter().position(|&id| id == p));
+            if let (Some(index), Some(info)) = (page_index, field_info(doc, name, field, widget)) {
+                pages[index].fields.push(info);
+            }
+        }
+    }
+
+    Ok(TemplateInfo {
+        page_count: pages.len(),
+        pages,
+    })
+}
+
+fn field_info(doc: &Document, name: &str, field: &FormField, widget_id: ObjectId) -> Option<FormFieldInfo> {
+    let widget = doc.get_dictionary(widget_id).ok()?;
+    let rect = widget.get(b"Rect").ok().and_then(|r| resolve_array(doc, r))?;
+    if rect.len() < 4 {
+        return None;
+    }
+    let mut coords = [0.0f32; 4];
+    for (slot, obj) in coords.iter_mut().zip(rect.iter()) {
+        *slot = obj.as_float().ok()?;
+    }
+    let [x1, y1, x2, y2] = coords;
+
+    let value = doc.get_dictionary(field.id).ok()
+        .and_then(|f| f.get(b"V").ok())
+        .and_then(|v| object_text(doc, v));
+
+    let on_state = match field.kind {
+        FieldKind::Checkbox | FieldKind::Radio => acroform::on_state(doc, widget_id)
+            .map(|s| String::from_utf8_lossy(&s).into_owned()),
+        _ => None,
+    };
+
+    Some(FormFieldInfo {
+        name: name.to_string(),
+        field_type: field.kind.name().to_string(),
+        rect: [x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)],
+        value,
+        options: field.options.iter().map(|(_, display)| display.clone()).collect(),
+        on_state,
+        max_len: field.max_len,
+        read_only: field.flags & FF_READ_ONLY != 0,
+        required: field.flags & FF_REQUIRED != 0,
+    })
+}
+
+/// A field value as text: names, text strings and arrays of either.
+fn object_text(doc: &Document, value: &Object) -> Option<String> {
+    match value {
+        Object::Reference(id) => object_text(doc, doc.get_object(*id).ok()?),
+        Object::Name(name) => Some(String::from_utf8_lossy(name).into_owned()),
+        Object::String(..) => lopdf::decode_text_string(value).ok(),
+        Object::Array(items) => {
+            let parts: Vec<String> = items.iter().filter_map(|i| object_text(doc, i)).collect();
+            Some(parts.join(", "))
+        }
+        _ => None,
+    }
+}
+
+/// `/Rotate`, inherited from the page tree like the page boxes.
+fn page_rotate(doc: &Document, page: &Dictionary) -> i64 {
+    let mut node = page;
+    for _ in 0..32 {
+        if let Ok(rotate) = node.get(b"Rotate").and_then(Object::as_i64) {
+            return rotate.rem_euclid(360);
+        }
+        let Some(parent) = node.get(b"Parent").and_then(Object::as_reference).ok()
+            .and_then(|id| doc.get_dictionary(id).ok())
+        else {
+            break;
+        };
+        node = parent;
+    }
+    0
+}
+
+/// Fonts referenced by the page's resources and the Form XObjects it draws.
+fn page_fonts(doc: &Document, page: &Dictionary) -> Vec<FontInfo> {
+    let mut fonts = Vec::new();
+    let mut seen_fonts = HashSet::new();
+    let mut seen_xobjects = HashSet::new();
+
+    let mut node = page;
+    let mut resources = None;
+    for _ in 0..32 {
+        if let Some(r) = node.get(b"Resources").ok().and_then(|r| resolve_dict(doc, r)) {
+            resources = Some(r);
+            break;
+        }
+        let Some(parent) = node.get(b"Parent").and_then(Object::as_reference).ok()
+            .and_then(|id| doc.get_dictionary(id).ok())
+        else {
+            break;
+        };
+        node = parent;
+    }
+
+    if let Some(resources) = resources {
+        collect_fonts(doc, resources, &mut fonts, &mut seen_fonts, &mut seen_xobjects, 0);
+    }
+    fonts
+}
+
+fn collect_fonts(
+    doc: &Document,
+    resources: &Dictionary,
+    fonts: &mut Vec<FontInfo>,
+    seen_fonts: &mut HashSet<(String, String)>,
+    seen_xobjects: &mut HashSet<ObjectId>,
+    depth: usize,
+) {
+    if depth > 8 {
+        return;
+    }
+
+    if let Some(font_dict) = resources.get(b"Font").ok().and_then(|f| resolve_dict(doc, f)) {
+        for (_, font) in font_dict.iter() {
+            let Some(font) = resolve_dict(doc, font) else {
+                continue;
+            };
+            let name = |key: &[u8]| font.get(key).and_then(Object::as_name)
+                .map(|n| String::from_utf8_lossy(n).into_owned())
+                .unwrap_or_default();
+            let base_font = name(b"BaseFont");
+            let subtype = name(b"Subtype");
+
+            if seen_fonts.insert((base_font.clone(), subtype.clone())) {
+                fonts.push(FontInfo {
+                    base_font,
+                    subtype,
+                    embedded: font_embedded(doc, font),
+                });
+            }
+        }
+    }
+
+    if let Some(xobjects) = resources.get(b"XObject").ok().and_then(|x| resolve_dict(doc, x)) {
+        for (_, xobject) in xobjects.iter() {
+            let Ok(id) = xobject.as_reference() else {
+                continue;
+            };
+            if !seen_xobjects.insert(id) {
+                continue;
+            }
+            let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+                continue;
+            };
+            if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form".as_slice()) {
+                continue;
+            }
+            if let Some(inner) = stream.dict.get(b"Resources").ok().and_then(|r| resolve_dict(doc, r)) {
+                collect_fonts(doc, inner, fonts, seen_fonts, seen_xobjects, depth + 1);
+            }
+        }
+    }
+}
+
+/// Whether a font (or a Type0 font's descendant) carries a font file.
+fn font_embedded(doc: &Document, font: &Dictionary) -> bool {
+    let descriptor_embedded = |font: &Dictionary| {
+        font.get(b"FontDescriptor").ok()
+            .and_then(|d| resolve_dict(doc, d))
+            .map(|d| d.has(b"FontFile") || d.has(b"FontFile2") || d.has(b"FontFile3"))
+            .unwrap_or(false)
+    };
+
+    if descriptor_embedded(font) {
+        return true;
+    }
+
+    font.get(b"DescendantFonts").ok()
+        .and_then(|d| resolve_array(doc, d))
+        .and_then(|d| d.first())
+        .and_then(|d| resolve_dict(doc, d))
+        .map(descriptor_embedded)
+        .unwrap_or(false)
+}
@@ -0,0 +1,43 @@
+//! Fill PDF templates with text, images, signatures and form field values.
+//!
+//! ```no_run
+//! use fill_pdf::{FieldData, FieldValue, Filler, TextOverflow};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let (pdf, metadata) = Filler::new("template.pdf")
+//!     .field(FieldData::named("applicant.name", FieldValue::Text("Jane Doe".into())))
+//!     .text_overflow(TextOverflow::Cutoff)
+//!     .fill()
+//!     .await?;
+//! std::fs::write("filled.pdf", pdf)?;
+//! println!("{} field(s) filled", metadata.fields_processed);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod batch;
+pub mod cache;
+pub mod inspect;
+pub mod merge;
+pub mod scaffold;
+pub mod server;
+pub mod template;
+pub mod types;
+
+mod acroform;
+mod barcode;
+mod color;
+mod fetcher;
+mod filler;
+mod fonts;
+mod images;
+mod metrics;
+mod renderer;
+mod shapes;
+mod signature;
+mod svg;
+
+pub use filler::{fill_prepared, FillOptions, Filler};
+pub use fonts::FontCache;
+pub use template::{load_template, parse_template_source, PreparedTemplate};
+pub use types::{FieldData, FieldValue, ImageFitMode, ImageSource, ProcessingMetadata, TemplateSource, TextOverflow, UrlConfig};
@@ -1,14 +1,9 @@
 use clap::{Parser, Subcommand};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
-mod renderer;
-mod types;
-mod merge;
-mod fetcher;
-mod cache;
-
-use renderer::PdfFieldRenderer;
-use types::{PdfDocument, FieldData, TemplateSource};
+use fill_pdf::{batch, cache, inspect, load_template, merge, scaffold, server, types};
+use fill_pdf::{fill_prepared, FieldData, FillOptions, Filler, FontCache, PreparedTemplate};
 
 #[derive(Parser)]
 #[command(name = "fill-pdf")]
@@ -58,13 +53,167 @@ enum Commands {
         #[arg(long)]
         keep_fields: bool,
         
-        /// Merge backend: python (PyPDF2) or bun (pdf-lib)
+        /// Merge backend: python (PyPDF2), bun (pdf-lib) or native (lopdf, no external runtime)
         #[arg(long, default_value = "python")]
         merge_backend: String,
         
-        /// Text overflow mode: overflow (default) or cutoff
+        /// Text overflow mode: overflow (default), cutoff, ellipsis, clip, shrink, auto_fit or continue
         #[arg(long, default_value = "overflow")]
         text_overflow: String,
+        
+        /// Directory searched for TTF/OTF fonts named by a field's "font"
+        #[arg(long)]
+        font_dir: Option<PathBuf>,
+        
+        /// Downsample images to at most this DPI at their placed size
+        #[arg(long)]
+        max_dpi: Option<f32>,
+        
+        /// JPEG quality (1-100) for re-encoded images (default: 85)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+        jpeg_quality: Option<u8>,
+    },
+    
+    /// Fill one template once per record of a CSV, JSON or JSON Lines file
+    FillBatch {
+        /// Path or URL to template PDF
+        #[arg(short, long)]
+        template: String,
+        
+        /// Records: .csv (header row = field ids), .json array, or JSON Lines
+        #[arg(short, long)]
+        records: PathBuf,
+        
+        /// Field data JSON whose fields each record fills by field_id
+        #[arg(short, long)]
+        layout: Option<PathBuf>,
+        
+        /// Directory for one PDF per record
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        
+        /// File name pattern; {index} is the record number, {key} a record value
+        #[arg(long, default_value = "record-{index}.pdf")]
+        output_pattern: String,
+        
+        /// Also write every filled record into one concatenated PDF
+        #[arg(long)]
+        concat: Option<PathBuf>,
+        
+        /// Write a per-record JSON summary (output, errors, metadata)
+        #[arg(long)]
+        summary: Option<PathBuf>,
+        
+        /// Enable template caching
+        #[arg(long)]
+        cache: bool,
+        
+        /// Cache directory (default: ~/.fill-pdf/cache)
+        #[arg(long)]
+        cache_dir: Option<PathBuf>,
+        
+        /// Cache TTL in seconds (default: 3600)
+        #[arg(long)]
+        cache_ttl: Option<i64>,
+        
+        /// Force cache refresh
+        #[arg(long)]
+        cache_refresh: bool,
+        
+        /// Keep interactive form fields (default: flatten)
+        #[arg(long)]
+        keep_fields: bool,
+        
+        /// Merge backend: python (PyPDF2), bun (pdf-lib) or native (lopdf, no external runtime)
+        #[arg(long, default_value = "python")]
+        merge_backend: String,
+        
+        /// Text overflow mode: overflow (default), cutoff, ellipsis, clip, shrink, auto_fit or continue
+        #[arg(long, default_value = "overflow")]
+        text_overflow: String,
+        
+        /// Directory searched for TTF/OTF fonts named by a field's "font"
+        #[arg(long)]
+        font_dir: Option<PathBuf>,
+        
+        /// Downsample images to at most this DPI at their placed size
+        #[arg(long)]
+        max_dpi: Option<f32>,
+        
+        /// JPEG quality (1-100) for re-encoded images (default: 85)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+        jpeg_quality: Option<u8>,
+    },
+    
+    /// Run a local HTTP server exposing fill and inspect as a REST API
+    Serve {
+        /// Address to bind
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        
+        /// Port to listen on
+        #[arg(short, long, default_value = "8080")]
+        port: u16,
+        
+        /// Enable template caching
+        #[arg(long)]
+        cache: bool,
+        
+        /// Cache directory (default: ~/.fill-pdf/cache)
+        #[arg(long)]
+        cache_dir: Option<PathBuf>,
+        
+        /// Cache TTL in seconds (default: 3600)
+        #[arg(long)]
+        cache_ttl: Option<i64>,
+        
+        /// Accept local file paths as templates (any client can then read PDFs this process can)
+        #[arg(long)]
+        allow_paths: bool,
+        
+        /// Keep interactive form fields by default (requests can override)
+        #[arg(long)]
+        keep_fields: bool,
+        
+        /// Merge backend: python (PyPDF2), bun (pdf-lib) or native (lopdf, no external runtime)
+        #[arg(long, default_value = "native")]
+        merge_backend: String,
+        
+        /// Default text overflow mode: overflow, cutoff, ellipsis, clip, shrink, auto_fit or continue (requests can override)
+        #[arg(long, default_value = "overflow")]
+        text_overflow: String,
+        
+        /// Directory searched for TTF/OTF fonts named by a field's "font"
+        #[arg(long)]
+        font_dir: Option<PathBuf>,
+        
+        /// Downsample images to at most this DPI at their placed size
+        #[arg(long)]
+        max_dpi: Option<f32>,
+        
+        /// JPEG quality (1-100) for re-encoded images (default: 85)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+        jpeg_quality: Option<u8>,
+    },
+    
+    /// Print a template's pages, fonts, annotations and form fields as JSON
+    Inspect {
+        /// Path, URL or JSON source config of the template PDF
+        template: String,
+        
+        /// Write the JSON to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    
+    /// Write starter field-data JSON from a template's form fields
+    Scaffold {
+        /// Path, URL or JSON source config of the template PDF
+        template: String,
+        
+        /// Write the JSON to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
     
     /// Convert PDF pages to images (PNG/JPEG)
@@ -111,13 +260,75 @@ enum CacheCommands {
     },
 }
 
+/// Prints the library's progress messages and warnings to stderr.
+struct ConsoleLogger;
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info && metadata.target().starts_with("fill_pdf")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            log::Level::Info => eprintln!("{}", record.args()),
+            _ => eprintln!("⚠️  {}", record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    log::set_logger(&LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info))
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Fill { template, data, output, metadata, cache, cache_dir, cache_ttl, cache_refresh, keep_fields, merge_backend, text_overflow } => {
-            fill_pdf(template, data, output, metadata, cache, cache_dir, cache_ttl, cache_refresh, keep_fields, merge_backend, text_overflow).await?;
+        Commands::Fill { template, data, output, metadata, cache, cache_dir, cache_ttl, cache_refresh, keep_fields, merge_backend, text_overflow, font_dir, max_dpi, jpeg_quality } => {
+            fill_pdf(template, data, output, metadata, cache, cache_dir, cache_ttl, cache_refresh, keep_fields, merge_backend, text_overflow, font_dir, max_dpi, jpeg_quality).await?;
+        }
+        Commands::FillBatch { template, records, layout, output_dir, output_pattern, concat, summary, cache, cache_dir, cache_ttl, cache_refresh, keep_fields, merge_backend, text_overflow, font_dir, max_dpi, jpeg_quality } => {
+            let options = FillOptions {
+                flatten: !keep_fields,
+                merge_backend,
+                text_overflow: parse_text_overflow(&text_overflow),
+                fonts: FontCache::new(font_dir),
+                max_dpi,
+                jpeg_quality,
+            };
+            let client = reqwest::Client::new();
+            let cache = cache.then(|| cache::TemplateCache::new(cache_dir, cache_ttl)).transpose()?;
+            let template_bytes = load_template(&client, &template, cache.as_ref(), cache_refresh).await?;
+            fill_batch(&client, template_bytes, records, layout, output_dir, output_pattern, concat, summary, options).await?;
+        }
+        Commands::Serve { host, port, cache, cache_dir, cache_ttl, allow_paths, keep_fields, merge_backend, text_overflow, font_dir, max_dpi, jpeg_quality } => {
+            ensure_dependencies(&merge_backend)?;
+            let state = server::ServerState {
+                client: reqwest::Client::new(),
+                cache: cache.then(|| cache::TemplateCache::new(cache_dir, cache_ttl)).transpose()?,
+                options: FillOptions {
+                    flatten: !keep_fields,
+                    merge_backend,
+                    text_overflow: parse_text_overflow(&text_overflow),
+                    fonts: FontCache::new(font_dir),
+                    max_dpi,
+                    jpeg_quality,
+                },
+                allow_paths,
+            };
+            server::serve(&host, port, state).await?;
+        }
+        Commands::Inspect { template, output } => {
+            inspect_template(template, output).await?;
+        }
+        Commands::Scaffold { template, output } => {
+            scaffold_template(template, output).await?;
         }
         Commands::ToImage { pdfs, output_dir, format, dpi, pages, base64 } => {
             pdf_to_images(pdfs, output_dir, format, dpi, pages, base64).await?;
@@ -136,6 +347,7 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn fill_pdf(
     template: String, 
     data: PathBuf, 
@@ -148,96 +360,40 @@ async fn fill_pdf(
     keep_fields: bool,
     merge_backend: String,
     text_overflow: String,
+    font_dir: Option<PathBuf>,
+    max_dpi: Option<f32>,
+    jpeg_quality: Option<u8>,
 ) -> anyhow::Result<()> {
     // Check dependencies first
-    merge::ensure_dependencies(&merge_backend)?;
-    
-    // Parse template source
-    let template_source: TemplateSource = if template.starts_with('{') {
-        serde_json::from_str(&template)?
-    } else if template.starts_with("http://") || template.starts_with("https://") {
-        TemplateSource::Url(types::UrlConfig {
-            url: template.clone(),
-            method: None,
-            headers: None,
-            body: None,
-        })
-    } else {
-        TemplateSource::Path(template.clone())
-    };
-    
-    // Load template bytes (with caching if enabled)
-    let template_bytes = if use_cache && !matches!(template_source, TemplateSource::Path(_)) {
-        let cache = cache::TemplateCache::new(cache_dir, cache_ttl)?;
-        let cache_key = cache::TemplateCache::generate_key(&template);
-        
-        if cache_refresh {
-            println!("🔄 Forcing cache refresh...");
-            fetch_and_cache_template(&template_source, &cache, &cache_key).await?
-        } else if let Some(entry) = cache.get(&cache_key) {
-            println!("✓ Using cached template");
-            
-            // Validate with server if we have ETag/Last-Modified
-            if entry.etag.is_some() || entry.last_modified.is_some() {
-                match validate_cache(&template_source, &entry).await {
-                    Ok(true) => entry.template_bytes,
-                    Ok(false) => {
-                        println!("🔄 Template updated, refreshing cache...");
-                        fetch_and_cache_template(&template_source, &cache, &cache_key).await?
-                    }
-                    Err(_) => {
-                        println!("⚠️  Cache validation failed, using cached version");
-                        entry.template_bytes
-                    }
-                }
-            } else {
-                entry.template_bytes
-            }
-        } else {
-            println!("📥 Fetching and caching template...");
-            fetch_and_cache_template(&template_source, &cache, &cache_key).await?
-        }
-    } else {
-        match template_source {
-            TemplateSource::Path(path) => std::fs::read(&path)?,
-            TemplateSource::Url(url_config) => {
-                println!("📥 Fetching template from URL...");
-                fetcher::fetch_url_with_config(&url_config).await?
-            }
-        }
-    };
-    
-    // Load PDF document using Cursor (same as srv-ocr)
-    let template_doc = lopdf::Document::load_from(std::io::Cursor::new(&template_bytes))
-        .map_err(|e| anyhow::anyhow!("Failed to load PDF document: {}", e))?;
-    let pdf_info = types::extract_pdf_info(&template_doc)?;
+    ensure_dependencies(&merge_backend)?;
     
     // Load field data
     let json_data = std::fs::read_to_string(&data)?;
-    let mut field_data: Vec<FieldData> = serde_json::from_str(&json_data)?;
-    
-    // Apply global text_overflow to fields without explicit setting
-    let global_overflow = match text_overflow.as_str() {
-        "cutoff" => types::TextOverflow::Cutoff,
-        _ => types::TextOverflow::Overflow,
-    };
-    
-    for field in &mut field_data {
-        if field.text_overflow.is_none() {
-            field.text_overflow = Some(global_overflow.clone());
-        }
+    let field_data: Vec<FieldData> = serde_json::from_str(&json_data)?;
+    
+    let mut filler = Filler::new(template)
+        .fields(field_data)
+        .flatten(!keep_fields)
+        .merge_backend(&merge_backend)
+        .text_overflow(parse_text_overflow(&text_overflow))
+        .cache_refresh(cache_refresh);
+    if use_cache {
+        filler = filler.cache(cache_dir, cache_ttl);
+    }
+    if let Some(dir) = font_dir {
+        filler = filler.font_dir(dir);
+    }
+    if let Some(dpi) = max_dpi {
+        filler = filler.max_dpi(dpi);
+    }
+    if let Some(quality) = jpeg_quality {
+        filler = filler.jpeg_quality(quality);
     }
     
-    // Fetch remote images/signatures
     println!("🖼️  Fetching remote images...");
-    let field_data = fetcher::fetch_remote_images(field_data).await?;
-    
-    // Create renderer and fill
-    let renderer = PdfFieldRenderer::new();
-    let (filled_pdf, metadata) = renderer.create_populated_form(&field_data, &pdf_info).await?;
-    
-    // Merge with template
-    let final_pdf = merge::merge_pdfs_bytes(&template_bytes, &filled_pdf, !keep_fields, &merge_backend)?;
+    let start = std::time::Instant::now();
+    let (final_pdf, metadata) = filler.fill().await?;
+    println!("⏱️  Fill completed in {:.2}ms using {}", start.elapsed().as_secs_f64() * 1000.0, merge_backend);
     
     // Save output
     std::fs::write(&output, final_pdf)?;
@@ -270,38 +426,174 @@ async fn fill_pdf(
     Ok(())
 }
 
+fn parse_text_overflow(value: &str) -> types::TextOverflow {
+    match value {
+        "cutoff" => types::TextOverflow::Cutoff,
+        "auto_fit" | "auto-fit" => types::TextOverflow::AutoFit,
+        "clip" => types::TextOverflow::Clip,
+        "ellipsis" => types::TextOverflow::Ellipsis,
+        "shrink" => types::TextOverflow::Shrink,
+        "continue" => types::TextOverflow::Continue,
+        _ => types::TextOverflow::Overflow,
+    }
+}
 
-async fn fetch_and_cache_template(
-    source: &TemplateSource,
-    cache: &cache::TemplateCache,
-    cache_key: &str,
-) -> anyhow::Result<Vec<u8>> {
-    let (bytes, etag, last_modified) = match source {
-        TemplateSource::Path(_) => unreachable!(),
-        TemplateSource::Url(config) => fetcher::fetch_with_headers(&config).await?,
-    };
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchRecordSummary {
+    index: usize,
+    output: Option<PathBuf>,
+    success: bool,
+    error: Option<String>,
+    metadata: Option<types::ProcessingMetadata>,
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn fill_batch(
+    client: &reqwest::Client,
+    template_bytes: Vec<u8>,
+    records_path: PathBuf,
+    layout_path: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    output_pattern: String,
+    concat: Option<PathBuf>,
+    summary_path: Option<PathBuf>,
+    options: FillOptions,
+) -> anyhow::Result<()> {
+    if output_dir.is_none() && concat.is_none() {
+        anyhow::bail!("Nothing to write: pass --output-dir, --concat or both");
+    }
+    ensure_dependencies(&options.merge_backend)?;
     
-    let entry = cache::CacheEntry {
-        template_bytes: bytes.clone(),
-        cached_at: chrono::Utc::now(),
-        etag,
-        last_modified,
+    let prepared = PreparedTemplate::new(template_bytes)?;
+    let layout: Vec<FieldData> = match &layout_path {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => Vec::new(),
     };
+    let records = batch::read_records(&records_path)?;
+    
+    if let Some(dir) = &output_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+    
+    println!("📦 Filling {} record(s)...", records.len());
+    let start = std::time::Instant::now();
+    
+    let mut summaries = Vec::with_capacity(records.len());
+    let mut filled = Vec::new();
+    
+    for (i, record) in records.iter().enumerate() {
+        let index = i + 1;
+        let result = async {
+            let fields = batch::record_fields(record, &layout)?;
+            let (pdf, metadata) = fill_prepared(client, &prepared, fields, &options).await?;
+            let output = match &output_dir {
+                Some(dir) => {
+                    let path = dir.join(batch::output_name(&output_pattern, index, record)?);
+                    std::fs::write(&path, &pdf)?;
+                    Some(path)
+                }
+                None => None,
+            };
+            anyhow::Ok((pdf, metadata, output))
+        }.await;
+        
+        match result {
+            Ok((pdf, metadata, output)) => {
+                match &output {
+                    Some(path) => println!("  ✓ [{}] {}", index, path.display()),
+                    None => println!("  ✓ [{}]", index),
+                }
+                if concat.is_some() {
+                    filled.push(pdf);
+                }
+                summaries.push(BatchRecordSummary {
+                    index,
+                    output,
+                    success: true,
+                    error: None,
+                    metadata: Some(metadata),
+                });
+            }
+            Err(e) => {
+                println!("  ✗ [{}] {}", index, e);
+                summaries.push(BatchRecordSummary {
+                    index,
+                    output: None,
+                    success: false,
+                    error: Some(e.to_string()),
+                    metadata: None,
+                });
+            }
+        }
+    }
+    
+    let failed = summaries.iter().filter(|s| !s.success).count();
+    println!("⏱️  Batch completed in {:.2}ms using {}", start.elapsed().as_secs_f64() * 1000.0, options.merge_backend);
+    println!("✓ {} of {} record(s) filled", summaries.len() - failed, summaries.len());
+    
+    if let Some(path) = &concat {
+        if filled.is_empty() {
+            println!("⚠️  No records filled, skipping {}", path.display());
+        } else {
+            std::fs::write(path, merge::concat_pdfs(&filled)?)?;
+            println!("📚 Concatenated PDF saved: {}", path.display());
+        }
+    }
+    
+    if let Some(path) = &summary_path {
+        std::fs::write(path, serde_json::to_string_pretty(&summaries)?)?;
+        println!("📊 Summary saved: {}", path.display());
+    }
+    
+    if failed > 0 {
+        anyhow::bail!("{} record(s) failed", failed);
+    }
     
-    cache.set(cache_key, entry)?;
-    Ok(bytes)
+    Ok(())
 }
 
-async fn validate_cache(
-    source: &TemplateSource,
-    entry: &cache::CacheEntry,
-) -> anyhow::Result<bool> {
-    match source {
-        TemplateSource::Path(_) => Ok(true),
-        TemplateSource::Url(config) => {
-            fetcher::validate_cache(&config, entry.etag.as_deref(), entry.last_modified.as_deref()).await
+async fn inspect_template(template: String, output: Option<PathBuf>) -> anyhow::Result<()> {
+    let template_bytes = load_template(&reqwest::Client::new(), &template, None, false).await?;
+    let template_doc = lopdf::Document::load_mem(&template_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to load PDF document: {}", e))?;
+    
+    let info = inspect::inspect_template(&template_doc)?;
+    let json = serde_json::to_string_pretty(&info)?;
+    
+    match output {
+        Some(path) => {
+            std::fs::write(&path, json)?;
+            println!("✓ Template info saved: {}", path.display());
         }
+        None => println!("{}", json),
     }
+    
+    Ok(())
+}
+
+async fn scaffold_template(template: String, output: Option<PathBuf>) -> anyhow::Result<()> {
+    let template_bytes = load_template(&reqwest::Client::new(), &template, None, false).await?;
+    let template_doc = lopdf::Document::load_mem(&template_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to load PDF document: {}", e))?;
+    
+    let info = inspect::inspect_template(&template_doc)?;
+    let fields = scaffold::scaffold_fields(&info);
+    let json = serde_json::to_string_pretty(&fields)?;
+    
+    match output {
+        Some(path) => {
+            std::fs::write(&path, json)?;
+            println!("✓ Scaffolded {} field(s): {}", fields.len(), path.display());
+        }
+        None => println!("{}", json),
+    }
+    
+    if fields.is_empty() {
+        eprintln!("⚠️  Template has no fillable form fields");
+    }
+    
+    Ok(())
 }
 
 async fn pdf_to_images(
@@ -472,6 +764,27 @@ except Exception as e:
     Ok(())
 }
 
+/// Check the merge backend's dependencies, offering to install a missing package.
+fn ensure_dependencies(backend: &str) -> anyhow::Result<()> {
+    let Some(package) = merge::check_dependencies(backend)? else {
+        return Ok(());
+    };
+    println!("⚠️  {} is not installed.", package.name());
+    print!("Would you like to install it now? (y/N): ");
+    io::stdout().flush()?;
+    
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().to_lowercase() != "y" {
+        anyhow::bail!("{} is required. Install with: {}", package.name(), package.install_command());
+    }
+    
+    println!("📦 Installing {}...", package.name());
+    package.install()?;
+    println!("✓ {} installed successfully", package.name());
+    Ok(())
+}
+
 async fn check_pdf_to_image_deps() -> anyhow::Result<()> {
     use std::process::Command;
     
@@ -484,7 +797,7 @@ async fn check_pdf_to_image_deps() -> anyhow::Result<()> {
     if !check_pdf2image.status.success() {
         println!("📦 Installing pdf2image...");
         let install = Command::new("pip3")
-            .args(&["install", "pdf2image", "--break-system-packages"])
+            .args(["install", "pdf2image", "--break-system-packages"])
             .output()?;
         
         if !install.status.success() {
@@ -517,13 +830,13 @@ async fn check_pdf_to_image_deps() -> anyhow::Result<()> {
         {
             // Try apt-get first (Ubuntu/Debian)
             let install = Command::new("sudo")
-                .args(&["apt-get", "install", "-y", "poppler-utils"])
+                .args(["apt-get", "install", "-y", "poppler-utils"])
                 .output();
             
             if install.is_err() || !install.unwrap().status.success() {
                 // Try yum (RHEL/CentOS)
                 let install = Command::new("sudo")
-                    .args(&["yum", "install", "-y", "poppler-utils"])
+                    .args(["yum", "install", "-y", "poppler-utils"])
                     .output()?;
                 
                 if !install.status.success() {
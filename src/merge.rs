@@ -1,63 +1,57 @@
 use std::process::Command;
-use std::io::{self, Write};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
 
-pub fn ensure_dependencies(backend: &str) -> anyhow::Result<()> {
-    match backend {
-        "python" => ensure_python_deps(),
-        "bun" => ensure_bun_deps(),
-        _ => anyhow::bail!("Unknown backend: {}. Use 'python' or 'bun'", backend),
-    }
+/// A package a merge backend needs that can be installed on request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Package {
+    PyPdf2,
+    PdfLib,
 }
 
-fn ensure_python_deps() -> anyhow::Result<()> {
-    // Check Python3
-    if !check_python3() {
-        anyhow::bail!("Python 3 is not installed. Please install Python 3 first.");
+impl Package {
+    pub fn name(self) -> &'static str {
+        match self {
+            Package::PyPdf2 => "PyPDF2",
+            Package::PdfLib => "pdf-lib",
+        }
     }
-    
-    // Check PyPDF2
-    if !check_pypdf2() {
-        println!("⚠️  PyPDF2 is not installed.");
-        print!("Would you like to install it now? (y/N): ");
-        io::stdout().flush()?;
-        
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        
-        if input.trim().to_lowercase() == "y" {
-            install_pypdf2()?;
-        } else {
-            anyhow::bail!("PyPDF2 is required. Install with: pip3 install PyPDF2");
+
+    /// Command that installs the package by hand.
+    pub fn install_command(self) -> &'static str {
+        match self {
+            Package::PyPdf2 => "pip3 install PyPDF2",
+            Package::PdfLib => "bun install pdf-lib",
         }
     }
-    
-    Ok(())
-}
 
-fn ensure_bun_deps() -> anyhow::Result<()> {
-    // Check Bun
-    if !check_bun() {
-        anyhow::bail!("Bun is not installed. Install from: https://bun.sh");
+    pub fn install(self) -> anyhow::Result<()> {
+        match self {
+            Package::PyPdf2 => install_pypdf2(),
+            Package::PdfLib => install_pdf_lib(),
+        }
     }
-    println!("✓ Bun runtime available");
-    
-    // Check pdf-lib
-    if !check_pdf_lib() {
-        println!("⚠️  pdf-lib is not installed.");
-        print!("Would you like to install it now? (y/N): ");
-        io::stdout().flush()?;
-        
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        
-        if input.trim().to_lowercase() == "y" {
-            install_pdf_lib()?;
-        } else {
-            anyhow::bail!("pdf-lib is required. Install with: bun install pdf-lib");
+}
+
+/// Check a backend's runtime and packages. Fails when the runtime itself is
+/// missing, and returns a missing package, which can be installed.
+pub fn check_dependencies(backend: &str) -> anyhow::Result<Option<Package>> {
+    match backend {
+        "python" => {
+            if !check_python3() {
+                anyhow::bail!("Python 3 is not installed. Please install Python 3 first.");
+            }
+            Ok((!check_pypdf2()).then_some(Package::PyPdf2))
         }
+        "bun" => {
+            if !check_bun() {
+                anyhow::bail!("Bun is not installed. Install from: https://bun.sh");
+            }
+            Ok((!check_pdf_lib()).then_some(Package::PdfLib))
+        }
+        // lopdf is compiled in, nothing to check
+        "native" => Ok(None),
+        _ => anyhow::bail!("Unknown backend: {}. Use 'python', 'bun' or 'native'", backend),
     }
-    
-    Ok(())
 }
 
 fn check_python3() -> bool {
@@ -78,8 +72,6 @@ fn check_pypdf2() -> bool {
 }
 
 fn install_pypdf2() -> anyhow::Result<()> {
-    println!("📦 Installing PyPDF2...");
-    
     // Try pip3 first (most common)
     let pip_commands = ["pip3", "pip", "python3 -m pip", "python -m pip"];
     
@@ -95,11 +87,10 @@ fn install_pypdf2() -> anyhow::Result<()> {
         for arg in args {
             command.arg(arg);
         }
-        command.args(&["install", "PyPDF2"]);
+        command.args(["install", "PyPDF2"]);
         
         if let Ok(output) = command.output() {
             if output.status.success() {
-                println!("✓ PyPDF2 installed successfully");
                 return Ok(());
             }
         }
@@ -112,26 +103,29 @@ fn install_pypdf2() -> anyhow::Result<()> {
     )
 }
 
-pub fn merge_pdfs_bytes(template_bytes: &[u8], overlay_pdf: &[u8], flatten: bool, backend: &str) -> anyhow::Result<Vec<u8>> {
-    let start = std::time::Instant::now();
-    
-    let result = if backend == "bun" {
-        merge_with_bun(template_bytes, overlay_pdf, flatten)?
-    } else {
-        merge_with_python(template_bytes, overlay_pdf, flatten)?
-    };
-    
-    let duration = start.elapsed();
-    println!("⏱️  Merge completed in {:.2}ms using {}", duration.as_secs_f64() * 1000.0, backend);
-    
-    Ok(result)
+/// Stamp overlay pages onto the template. `page_map[i]` is the template page
+/// index that overlay page `i` belongs on (see `ProcessingMetadata::page_map`).
+pub fn merge_pdfs_bytes(
+    template_bytes: &[u8],
+    overlay_pdf: &[u8],
+    page_map: &[u32],
+    flatten: bool,
+    backend: &str,
+) -> anyhow::Result<Vec<u8>> {
+    match backend {
+        "bun" => merge_with_bun(template_bytes, overlay_pdf, page_map, flatten),
+        "native" => merge_with_native(template_bytes, overlay_pdf, page_map, flatten),
+        _ => merge_with_python(template_bytes, overlay_pdf, page_map, flatten),
+    }
 }
 
-fn merge_with_python(template_bytes: &[u8], overlay_pdf: &[u8], flatten: bool) -> anyhow::Result<Vec<u8>> {
-    let temp_dir = std::env::temp_dir();
-    let temp_template = temp_dir.join("fill_pdf_template.pdf");
-    let temp_overlay = temp_dir.join("fill_pdf_overlay.pdf");
-    let temp_merged = temp_dir.join("fill_pdf_merged.pdf");
+fn merge_with_python(template_bytes: &[u8], overlay_pdf: &[u8], page_map: &[u32], flatten: bool) -> anyhow::Result<Vec<u8>> {
+    // A directory of its own per merge, so concurrent fills don't share files;
+    // it is removed when dropped
+    let temp_dir = tempfile::Builder::new().prefix("fill_pdf_").tempdir()?;
+    let temp_template = temp_dir.path().join("template.pdf");
+    let temp_overlay = temp_dir.path().join("overlay.pdf");
+    let temp_merged = temp_dir.path().join("merged.pdf");
     
     std::fs::write(&temp_template, template_bytes)?;
     std::fs::write(&temp_overlay, overlay_pdf)?;
@@ -156,12 +150,15 @@ try:
     
     template = PdfReader('{}')
     overlay = PdfReader('{}')
+    page_map = {:?}
     
     writer = PdfWriter()
     
-    for i, page in enumerate(template.pages):
-        if i < len(overlay.pages):
-            page.merge_page(overlay.pages[i])
+    for overlay_index, template_index in enumerate(page_map):
+        if overlay_index < len(overlay.pages) and template_index < len(template.pages):
+            template.pages[template_index].merge_page(overlay.pages[overlay_index])
+    
+    for page in template.pages:
         writer.add_page(page)
     {}
     with open('{}', 'wb') as output:
@@ -175,7 +172,7 @@ except ImportError:
 except Exception as e:
     print(f"ERROR: {{e}}")
     sys.exit(1)
-"#, temp_template.display(), temp_overlay.display(), flatten_code, temp_merged.display());
+"#, temp_template.display(), temp_overlay.display(), page_map, flatten_code, temp_merged.display());
     
     let output = Command::new("python3")
         .arg("-c")
@@ -187,19 +184,14 @@ except Exception as e:
     }
     
     let merged = std::fs::read(&temp_merged)?;
-    
-    let _ = std::fs::remove_file(&temp_template);
-    let _ = std::fs::remove_file(&temp_overlay);
-    let _ = std::fs::remove_file(&temp_merged);
-    
     Ok(merged)
 }
 
-fn merge_with_bun(template_bytes: &[u8], overlay_pdf: &[u8], flatten: bool) -> anyhow::Result<Vec<u8>> {
-    let temp_dir = std::env::temp_dir();
-    let temp_template = temp_dir.join("fill_pdf_template_bun.pdf");
-    let temp_overlay = temp_dir.join("fill_pdf_overlay_bun.pdf");
-    let temp_merged = temp_dir.join("fill_pdf_merged_bun.pdf");
+fn merge_with_bun(template_bytes: &[u8], overlay_pdf: &[u8], page_map: &[u32], flatten: bool) -> anyhow::Result<Vec<u8>> {
+    let temp_dir = tempfile::Builder::new().prefix("fill_pdf_").tempdir()?;
+    let temp_template = temp_dir.path().join("template.pdf");
+    let temp_overlay = temp_dir.path().join("overlay.pdf");
+    let temp_merged = temp_dir.path().join("merged.pdf");
     
     std::fs::write(&temp_template, template_bytes)?;
     std::fs::write(&temp_overlay, overlay_pdf)?;
@@ -211,7 +203,8 @@ fn merge_with_bun(template_bytes: &[u8], overlay_pdf: &[u8], flatten: bool) -> a
         .arg(&script_path)
         .arg("--template").arg(&temp_template)
         .arg("--overlay").arg(&temp_overlay)
-        .arg("--output").arg(&temp_merged);
+        .arg("--output").arg(&temp_merged)
+        .arg("--page-map").arg(page_map.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(","));
     
     if flatten {
         cmd.arg("--flatten");
@@ -225,14 +218,510 @@ fn merge_with_bun(template_bytes: &[u8], overlay_pdf: &[u8], flatten: bool) -> a
     }
     
     let merged = std::fs::read(&temp_merged)?;
+    Ok(merged)
+}
+
+/// Merge in-process with lopdf: each overlay page is imported as a Form XObject
+/// and stamped on top of the matching template page.
+fn merge_with_native(template_bytes: &[u8], overlay_pdf: &[u8], page_map: &[u32], flatten: bool) -> anyhow::Result<Vec<u8>> {
+    let template = Document::load_mem(template_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to load template PDF: {}", e))?;
+    merge_into_document(template, overlay_pdf, page_map, flatten)
+}
+
+/// Native merge onto an already parsed template, so callers filling the same
+/// template repeatedly can parse it once and clone it.
+pub fn merge_into_document(mut template: Document, overlay_pdf: &[u8], page_map: &[u32], flatten: bool) -> anyhow::Result<Vec<u8>> {
+    let mut overlay = Document::load_mem(overlay_pdf)
+        .map_err(|e| anyhow::anyhow!("Failed to load overlay PDF: {}", e))?;
+    
+    // Move overlay objects above the template's id range so both sets can live in one document
+    overlay.renumber_objects_with(template.max_id + 1);
+    let overlay_pages: Vec<ObjectId> = overlay.get_pages().into_values().collect();
+    let overlay_fields = overlay.catalog().ok()
+        .and_then(|cat| cat.get(b"AcroForm").ok())
+        .and_then(|form| resolve_dict(&overlay, form))
+        .and_then(|form| form.get(b"Fields").ok())
+        .and_then(|fields| resolve_array(&overlay, fields))
+        .cloned()
+        .unwrap_or_default();
+    
+    let mut overlay_page_data = Vec::new();
+    for page_id in &overlay_pages {
+        let page = overlay.get_dictionary(*page_id)?;
+        let content = overlay.get_page_content(*page_id)?;
+        let resources = page.get(b"Resources").cloned().unwrap_or(Object::Dictionary(Dictionary::new()));
+        let media_box = page.get(b"MediaBox").cloned().unwrap_or(Object::Array(vec![
+            0.into(), 0.into(), 595.into(), 842.into(),
+        ]));
+        let annots = page.get(b"Annots").ok()
+            .and_then(|annots| resolve_array(&overlay, annots))
+            .cloned()
+            .unwrap_or_default();
+        overlay_page_data.push((content, resources, media_box, annots));
+    }
+    
+    template.max_id = overlay.max_id;
+    template.objects.extend(std::mem::take(&mut overlay.objects));
+    
+    let template_pages: Vec<ObjectId> = template.get_pages().into_values().collect();
+    
+    for (i, (content, resources, media_box, annots)) in overlay_page_data.into_iter().enumerate() {
+        let Some(&page_id) = page_map.get(i).and_then(|&p| template_pages.get(p as usize)) else {
+            continue;
+        };
+        
+        let mut form_dict = Dictionary::new();
+        form_dict.set("Type", "XObject");
+        form_dict.set("Subtype", "Form");
+        form_dict.set("BBox", media_box);
+        form_dict.set("Resources", resources);
+        let mut form = Stream::new(form_dict, content);
+        let _ = form.compress();
+        let form_id = template.add_object(form);
+        
+        // Overlay coordinates are relative to the template page's box origin
+        let (origin_x, origin_y) = template.get_dictionary(page_id).ok()
+            .and_then(|page| crate::types::page_box(&template, page, b"MediaBox"))
+            .map(|[x1, y1, _, _]| (x1, y1))
+            .unwrap_or((0.0, 0.0));
+        
+        let name = add_page_xobject(&mut template, page_id, "FillPdfOverlay", form_id)?;
+        let stamp = format!(
+            "q 1 0 0 1 {} {} cm /{} Do Q\n",
+            origin_x, origin_y, String::from_utf8_lossy(&name)
+        );
+        wrap_page_contents(&mut template, page_id, stamp.into_bytes())?;
+        
+        if !annots.is_empty() {
+            append_page_annotations(&mut template, page_id, annots)?;
+        }
+    }
+    
+    if !overlay_fields.is_empty() {
+        append_form_fields(&mut template, overlay_fields)?;
+    }
+    
+    if flatten {
+        flatten_form(&mut template)?;
+    }
     
-    let _ = std::fs::remove_file(&temp_template);
-    let _ = std::fs::remove_file(&temp_overlay);
-    let _ = std::fs::remove_file(&temp_merged);
+    template.prune_objects();
     
+    let mut merged = Vec::new();
+    template.save_to(&mut merged)?;
     Ok(merged)
 }
 
+/// Flatten interactive form fields: every widget's current appearance is drawn
+/// into its page content, then the widgets and the AcroForm are removed.
+pub fn flatten_form(doc: &mut Document) -> anyhow::Result<()> {
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    
+    for page_id in pages {
+        let annots = doc.get_dictionary(page_id).ok()
+            .and_then(|page| page.get(b"Annots").ok())
+            .and_then(|annots| resolve_array(doc, annots))
+            .cloned()
+            .unwrap_or_default();
+        
+        if annots.is_empty() {
+            continue;
+        }
+        
+        let mut kept = Vec::new();
+        let mut draws = Vec::new();
+        
+        for annot in annots {
+            let Some(dict) = resolve_dict(doc, &annot) else {
+                continue;
+            };
+            
+            if dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Widget".as_slice()) {
+                kept.push(annot);
+                continue;
+            }
+            
+            // Hidden widgets (flag bit 2) are dropped without drawing
+            let flags = dict.get(b"F").and_then(Object::as_i64).unwrap_or(0);
+            if flags & 2 != 0 {
+                continue;
+            }
+            
+            if let Some((appearance_id, rect)) = widget_appearance(doc, dict) {
+                draws.push((appearance_id, rect));
+            }
+        }
+        
+        let mut stamp = String::new();
+        for (appearance_id, [x1, y1, x2, y2]) in draws {
+            let Some(matrix) = appearance_matrix(doc, appearance_id, [x1, y1, x2, y2]) else {
+                continue;
+            };
+            let name = add_page_xobject(doc, page_id, "FillPdfField", appearance_id)?;
+            stamp.push_str(&format!(
+                "q {} {} {} {} {} {} cm /{} Do Q\n",
+                matrix[0], matrix[1], matrix[2], matrix[3], matrix[4], matrix[5],
+                String::from_utf8_lossy(&name)
+            ));
+        }
+        
+        if !stamp.is_empty() {
+            wrap_page_contents(doc, page_id, stamp.into_bytes())?;
+        }
+        
+        let page = doc.get_dictionary_mut(page_id)?;
+        if kept.is_empty() {
+            page.remove(b"Annots");
+        } else {
+            page.set("Annots", kept);
+        }
+    }
+    
+    doc.catalog_mut()?.remove(b"AcroForm");
+    Ok(())
+}
+
+/// Pick the normal appearance stream of a widget, honoring its `/AS` state.
+fn widget_appearance(doc: &Document, widget: &Dictionary) -> Option<(ObjectId, [f32; 4])> {
+    let rect = widget.get(b"Rect").ok().and_then(|r| resolve_array(doc, r))?;
+    if rect.len() < 4 {
+        return None;
+    }
+    let mut coords = [0.0f32; 4];
+    for (slot, obj) in coords.iter_mut().zip(rect.iter()) {
+        *slot = obj.as_float().ok()?;
+    }
+    let [x1, y1, x2, y2] = coords;
+    let rect = [x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)];
+    
+    let ap = widget.get(b"AP").ok().and_then(|ap| resolve_dict(doc, ap))?;
+    let normal = ap.get(b"N").ok()?;
+    
+    let appearance_id = match normal {
+        Object::Reference(id) if doc.get_object(*id).and_then(Object::as_stream).is_ok() => *id,
+        other => {
+            let states = resolve_dict(doc, other)?;
+            let state = widget.get(b"AS").and_then(Object::as_name).ok()?;
+            states.get(state).and_then(Object::as_reference).ok()?
+        }
+    };
+    
+    Some((appearance_id, rect))
+}
+
+/// Matrix that maps an appearance stream's (transformed) bounding box onto the
+/// annotation rectangle, as described in PDF 32000 section 12.5.5.
+fn appearance_matrix(doc: &Document, appearance_id: ObjectId, rect: [f32; 4]) -> Option<[f32; 6]> {
+    let stream = doc.get_object(appearance_id).and_then(Object::as_stream).ok()?;
+    
+    let read_numbers = |key: &[u8]| -> Option<Vec<f32>> {
+        let arr = stream.dict.get(key).ok().and_then(|o| resolve_array(doc, o))?;
+        arr.iter().map(|o| o.as_float().ok()).collect()
+    };
+    
+    let bbox = read_numbers(b"BBox").filter(|b| b.len() == 4)?;
+    let m = read_numbers(b"Matrix")
+        .filter(|m| m.len() == 6)
+        .unwrap_or_else(|| vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    
+    let corners = [(bbox[0], bbox[1]), (bbox[2], bbox[1]), (bbox[0], bbox[3]), (bbox[2], bbox[3])];
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for (x, y) in corners {
+        let tx = m[0] * x + m[2] * y + m[4];
+        let ty = m[1] * x + m[3] * y + m[5];
+        min_x = min_x.min(tx);
+        min_y = min_y.min(ty);
+        max_x = max_x.max(tx);
+        max_y = max_y.max(ty);
+    }
+    
+    let bbox_width = max_x - min_x;
+    let bbox_height = max_y - min_y;
+    if bbox_width <= 0.0 || bbox_height <= 0.0 {
+        return None;
+    }
+    
+    let sx = (rect[2] - rect[0]) / bbox_width;
+    let sy = (rect[3] - rect[1]) / bbox_height;
+    Some([sx, 0.0, 0.0, sy, rect[0] - sx * min_x, rect[1] - sy * min_y])
+}
+
+/// Isolate the existing page content in `q`/`Q` and append `extra` after it.
+fn wrap_page_contents(doc: &mut Document, page_id: ObjectId, extra: Vec<u8>) -> anyhow::Result<()> {
+    let existing: Vec<Object> = doc.get_page_contents(page_id)
+        .into_iter()
+        .map(Object::Reference)
+        .collect();
+    
+    let open_id = doc.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()));
+    let mut close = b"\nQ\n".to_vec();
+    close.extend(extra);
+    let mut close = Stream::new(Dictionary::new(), close);
+    let _ = close.compress();
+    let close_id = doc.add_object(close);
+    
+    let mut contents = vec![Object::Reference(open_id)];
+    contents.extend(existing);
+    contents.push(Object::Reference(close_id));
+    
+    doc.get_dictionary_mut(page_id)?.set("Contents", contents);
+    Ok(())
+}
+
+/// Register `xobject_id` in the page's resources under a fresh name.
+fn add_page_xobject(doc: &mut Document, page_id: ObjectId, prefix: &str, xobject_id: ObjectId) -> anyhow::Result<Vec<u8>> {
+    let mut resources = page_resources(doc, page_id);
+    
+    let mut xobjects = resources.get(b"XObject").ok()
+        .and_then(|x| resolve_dict(doc, x))
+        .cloned()
+        .unwrap_or_default();
+    
+    let mut index = 0;
+    let name = loop {
+        let candidate = format!("{}{}", prefix, index).into_bytes();
+        if !xobjects.has(&candidate) {
+            break candidate;
+        }
+        index += 1;
+    };
+    
+    xobjects.set(name.clone(), Object::Reference(xobject_id));
+    resources.set("XObject", xobjects);
+    doc.get_dictionary_mut(page_id)?.set("Resources", resources);
+    Ok(name)
+}
+
+/// Effective resources of a page as an owned dictionary. Inherited or shared
+/// resource dictionaries are copied so edits stay local to the page.
+fn page_resources(doc: &Document, page_id: ObjectId) -> Dictionary {
+    let Ok(page) = doc.get_dictionary(page_id) else {
+        return Dictionary::new();
+    };
+    
+    let mut node = page;
+    for _ in 0..32 {
+        if let Some(resources) = node.get(b"Resources").ok().and_then(|r| resolve_dict(doc, r)) {
+            return resources.clone();
+        }
+        let Some(parent) = node.get(b"Parent").ok()
+            .and_then(|p| p.as_reference().ok())
+            .and_then(|id| doc.get_dictionary(id).ok())
+        else {
+            break;
+        };
+        node = parent;
+    }
+    Dictionary::new()
+}
+
+fn append_page_annotations(doc: &mut Document, page_id: ObjectId, annots: Vec<Object>) -> anyhow::Result<()> {
+    let mut existing = doc.get_dictionary(page_id).ok()
+        .and_then(|page| page.get(b"Annots").ok())
+        .and_then(|a| resolve_array(doc, a))
+        .cloned()
+        .unwrap_or_default();
+    
+    for annot in &annots {
+        if let Ok(annot_id) = annot.as_reference() {
+            if let Ok(dict) = doc.get_dictionary_mut(annot_id) {
+                dict.set("P", Object::Reference(page_id));
+            }
+        }
+    }
+    
+    existing.extend(annots);
+    doc.get_dictionary_mut(page_id)?.set("Annots", existing);
+    Ok(())
+}
+
+fn append_form_fields(doc: &mut Document, fields: Vec<Object>) -> anyhow::Result<()> {
+    let acro_form = doc.catalog()?.get(b"AcroForm").ok().cloned();
+    
+    let mut form = match &acro_form {
+        Some(obj) => resolve_dict(doc, obj).cloned().unwrap_or_default(),
+        None => Dictionary::new(),
+    };
+    
+    let mut existing = form.get(b"Fields").ok()
+        .and_then(|f| resolve_array(doc, f))
+        .cloned()
+        .unwrap_or_default();
+    existing.extend(fields);
+    form.set("Fields", existing);
+    
+    match acro_form {
+        Some(Object::Reference(id)) => {
+            *doc.get_object_mut(id)? = Object::Dictionary(form);
+        }
+        _ => {
+            doc.catalog_mut()?.set("AcroForm", form);
+        }
+    }
+    Ok(())
+}
+
+/// Append overlay pages `first..` to the end of a filled PDF as pages of
+/// their own rather than stamps (continuation sheets).
+pub fn append_overlay_pages(pdf: &[u8], overlay_pdf: &[u8], first: usize) -> anyhow::Result<Vec<u8>> {
+    let mut doc = Document::load_mem(pdf)
+        .map_err(|e| anyhow::anyhow!("Failed to load filled PDF: {}", e))?;
+    let mut overlay = Document::load_mem(overlay_pdf)
+        .map_err(|e| anyhow::anyhow!("Failed to load overlay PDF: {}", e))?;
+    
+    overlay.renumber_objects_with(doc.max_id + 1);
+    let pages: Vec<ObjectId> = overlay.get_pages().into_values().skip(first).collect();
+    if pages.is_empty() {
+        return Ok(pdf.to_vec());
+    }
+    doc.max_id = overlay.max_id;
+    doc.objects.extend(overlay.objects);
+    
+    let tree_id = doc.catalog()?.get(b"Pages")?.as_reference()?;
+    for &page_id in &pages {
+        doc.get_dictionary_mut(page_id)?.set("Parent", Object::Reference(tree_id));
+    }
+    let mut kids = doc.get_dictionary(tree_id)?.get(b"Kids").ok()
+        .and_then(|kids| resolve_array(&doc, kids))
+        .cloned()
+        .unwrap_or_default();
+    kids.extend(pages.iter().map(|&page_id| Object::Reference(page_id)));
+    let tree = doc.get_dictionary_mut(tree_id)?;
+    let count = tree.get(b"Count").and_then(Object::as_i64).unwrap_or(0);
+    tree.set("Count", count + pages.len() as i64);
+    tree.set("Kids", kids);
+    
+    // Drops the overlay's catalog, page tree and stamped pages
+    doc.prune_objects();
+    
+    let mut merged = Vec::new();
+    doc.save_to(&mut merged)?;
+    Ok(merged)
+}
+
+/// Concatenate PDFs page by page into one document. Each input's form fields
+/// are nested under a `recordN` parent field so equal names stay independent.
+pub fn concat_pdfs(pdfs: &[Vec<u8>]) -> anyhow::Result<Vec<u8>> {
+    let mut output = Document::with_version("1.7");
+    let pages_id = output.new_object_id();
+    let mut kids = Vec::new();
+    let mut fields = Vec::new();
+    let mut form_defaults: Option<Dictionary> = None;
+    
+    for (index, bytes) in pdfs.iter().enumerate() {
+        let mut doc = Document::load_mem(bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to load PDF {}: {}", index + 1, e))?;
+        doc.renumber_objects_with(output.max_id + 1);
+        
+        let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+        
+        // Pages are re-parented, so copy attributes they inherited from the old tree
+        for &page_id in &page_ids {
+            let mut inherited = Vec::new();
+            for key in [b"Resources".as_slice(), b"MediaBox", b"CropBox", b"Rotate"] {
+                if doc.get_dictionary(page_id)?.has(key) {
+                    continue;
+                }
+                if let Some(value) = inherited_attribute(&doc, page_id, key) {
+                    inherited.push((key.to_vec(), value));
+                }
+            }
+            let page = doc.get_dictionary_mut(page_id)?;
+            for (key, value) in inherited {
+                page.set(key, value);
+            }
+            page.set("Parent", Object::Reference(pages_id));
+            kids.push(Object::Reference(page_id));
+        }
+        
+        let acro_form = doc.catalog().ok()
+            .and_then(|cat| cat.get(b"AcroForm").ok())
+            .and_then(|form| resolve_dict(&doc, form))
+            .cloned();
+        let record_fields = acro_form.as_ref()
+            .and_then(|form| form.get(b"Fields").ok())
+            .and_then(|f| resolve_array(&doc, f))
+            .cloned()
+            .unwrap_or_default();
+        
+        // Default resources and appearance (/DR, /DA, ...) come from the first form
+        if form_defaults.is_none() {
+            form_defaults = acro_form;
+        }
+        
+        output.max_id = doc.max_id;
+        output.objects.extend(doc.objects);
+        
+        if !record_fields.is_empty() {
+            let parent_id = output.new_object_id();
+            for field in &record_fields {
+                if let Ok(dict) = field.as_reference().and_then(|id| output.get_dictionary_mut(id)) {
+                    dict.set("Parent", Object::Reference(parent_id));
+                }
+            }
+            let mut parent = Dictionary::new();
+            parent.set("T", Object::string_literal(format!("record{}", index + 1)));
+            parent.set("Kids", record_fields);
+            output.objects.insert(parent_id, Object::Dictionary(parent));
+            fields.push(Object::Reference(parent_id));
+        }
+    }
+    
+    let mut pages = Dictionary::new();
+    pages.set("Type", "Pages");
+    pages.set("Count", kids.len() as i64);
+    pages.set("Kids", kids);
+    output.objects.insert(pages_id, Object::Dictionary(pages));
+    
+    let mut catalog = Dictionary::new();
+    catalog.set("Type", "Catalog");
+    catalog.set("Pages", Object::Reference(pages_id));
+    if !fields.is_empty() {
+        let mut form = form_defaults.unwrap_or_default();
+        form.set("Fields", fields);
+        catalog.set("AcroForm", form);
+    }
+    let catalog_id = output.add_object(catalog);
+    output.trailer.set("Root", Object::Reference(catalog_id));
+    
+    // Drops the inputs' old catalogs and page tree nodes
+    output.prune_objects();
+    
+    let mut merged = Vec::new();
+    output.save_to(&mut merged)?;
+    Ok(merged)
+}
+
+/// Value of an inheritable page attribute from the page's ancestors.
+fn inherited_attribute(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<Object> {
+    let mut node = doc.get_dictionary(page_id).ok()?;
+    for _ in 0..32 {
+        let parent = node.get(b"Parent").and_then(Object::as_reference).ok()?;
+        node = doc.get_dictionary(parent).ok()?;
+        if let Ok(value) = node.get(key) {
+            return Some(value.clone());
+        }
+    }
+    None
+}
+
+pub(crate) fn resolve_dict<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Dictionary> {
+    match obj {
+        Object::Reference(id) => doc.get_dictionary(*id).ok(),
+        Object::Dictionary(dict) => Some(dict),
+        _ => None,
+    }
+}
+
+pub(crate) fn resolve_array<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Vec<Object>> {
+    match obj {
+        Object::Reference(id) => doc.get_object(*id).and_then(Object::as_array).ok(),
+        Object::Array(arr) => Some(arr),
+        _ => None,
+    }
+}
+
 fn check_bun() -> bool {
     Command::new("bun")
         .arg("--version")
@@ -254,15 +743,12 @@ fn check_pdf_lib() -> bool {
 }
 
 fn install_pdf_lib() -> anyhow::Result<()> {
-    println!("📦 Installing pdf-lib...");
-    
     let output = Command::new("bun")
         .arg("install")
         .arg("pdf-lib")
         .output()?;
     
     if output.status.success() {
-        println!("✓ pdf-lib installed successfully");
         Ok(())
     } else {
         anyhow::bail!(
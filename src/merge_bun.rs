@@ -33,7 +33,7 @@ pub fn merge_with_bun(template_bytes: &[u8], overlay_pdf: &[u8], flatten: bool)
     // Extract timing from output
     let stdout = String::from_utf8_lossy(&output.stdout);
     if let Some(timing) = stdout.strip_prefix("SUCCESS:") {
-        println!("  (Bun: {})", timing.trim());
+        log::info!("Bun: {}", timing.trim());
     }
     
     let merged = std::fs::read(&temp_merged)?;
@@ -0,0 +1,46 @@
+use crate::fonts::encode_win_ansi;
+
+/// Helvetica advance widths from the Adobe AFM, indexed by WinAnsiEncoding
+/// byte, in 1/1000 em. Unassigned codes are 0; `encode_win_ansi` never
+/// produces them.
+#[rustfmt::skip]
+const HELVETICA_WIDTHS: [u16; 256] = [
+    // 0x00-0x1F: control codes
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    // 0x20: space ! " # $ % & ' ( ) * + , - . /
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    // 0x30: 0-9 : ; < = > ?
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    // 0x40: @ A-O
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    // 0x50: P-Z [ \ ] ^ _
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    // 0x60: ` a-o
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    // 0x70: p-z { | } ~
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, 0,
+    // 0x80: € , ƒ „ … † ‡ ˆ ‰ Š ‹ Œ Ž
+    556, 0, 222, 556, 333, 1000, 556, 556, 333, 1000, 667, 333, 1000, 0, 611, 0,
+    // 0x90: ' ' " " • – — ˜ ™ š › œ ž Ÿ
+    0, 222, 222, 333, 333, 350, 556, 1000, 333, 1000, 500, 333, 944, 0, 500, 667,
+    // 0xA0: nbsp ¡ ¢ £ ¤ ¥ ¦ § ¨ © ª « ¬ shy ® ¯
+    278, 333, 556, 556, 556, 556, 260, 556, 333, 737, 370, 556, 584, 333, 737, 333,
+    // 0xB0: ° ± ² ³ ´ µ ¶ · ¸ ¹ º » ¼ ½ ¾ ¿
+    400, 584, 333, 333, 333, 556, 537, 278, 333, 333, 365, 556, 834, 834, 834, 611,
+    // 0xC0: À-Ï
+    667, 667, 667, 667, 667, 667, 1000, 722, 667, 667, 667, 667, 278, 278, 278, 278,
+    // 0xD0: Ð-ß
+    722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722, 722, 667, 667, 611,
+    // 0xE0: à-ï
+    556, 556, 556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278, 278,
+    // 0xF0: ð-ÿ
+    556, 556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500, 556, 500,
+];
+
+/// Width of `text` set in Helvetica at `font_size`, in points.
+pub fn helvetica_width(text: &str, font_size: f32) -> f32 {
+    let (bytes, _) = encode_win_ansi(text);
+    let units: u32 = bytes.iter().map(|&b| HELVETICA_WIDTHS[b as usize] as u32).sum();
+    units as f32 / 1000.0 * font_size
+}
@@ -1,7 +1,55 @@
-use crate::types::{FieldData, FieldValue, ImageSource, ImageFitMode, PdfDocument, ProcessingMetadata, PageMetadata};
-use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, Filter};
+use crate::barcode;
+use crate::color::Color;
+use crate::fonts::{self, FontCache, FontKey, FontRegistry};
+use crate::images::{self, EncodedImage};
+use image::metadata::Orientation;
+use crate::metrics;
+use crate::shapes;
+use crate::signature;
+use crate::svg::{self, SvgImage};
+use crate::types::{FieldData, FieldValue, ImageSource, ImageFitMode, ImageMetadata, PdfDocument, ProcessingMetadata, PageMetadata, StrokeSignature, Symbology, TextFitMetadata, TextOverflow, TypedSignature, ContinuationMetadata};
+use pdf_writer::types::TextRenderingMode;
+use pdf_writer::writers::Resources;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
 use std::collections::HashMap;
 
+/// Outline width of synthesized bold text, per point of font size
+const BOLD_STROKE: f32 = 0.03;
+/// Horizontal slant of synthesized italic text (about 12°)
+const ITALIC_SKEW: f32 = 0.21;
+
+/// Marks text cut short by the `ellipsis` overflow mode
+const ELLIPSIS: &str = "\u{2026}";
+
+/// Last line of a `continue` box whose text goes on in a continuation sheet
+const CONTINUATION_MARKER: &str = "(see continuation sheet)";
+
+/// Margin around continuation sheet text
+const SHEET_MARGIN: f32 = 72.0;
+
+/// Font size of a continuation sheet's title
+const SHEET_TITLE_SIZE: f32 = 14.0;
+
+/// Space above continuation sheet text for its title and source line
+const SHEET_HEADING_HEIGHT: f32 = 40.0;
+
+/// Smallest size `auto_fit` and `shrink` go to without a `min_font_size`
+const DEFAULT_MIN_FONT_SIZE: f32 = 6.0;
+
+/// Largest font size of a typed signature's caption
+const CAPTION_FONT_SIZE: f32 = 7.0;
+
+/// An image or SVG form XObject and what it takes to draw it upright.
+#[derive(Clone, Copy)]
+struct EmbeddedImage {
+    id: Ref,
+    /// Stored pixel size of the original image, before any resampling, or
+    /// the SVG's viewport size
+    width: f32,
+    height: f32,
+    orientation: Orientation,
+}
+
 pub struct PdfFieldRenderer {
     pdf: Pdf,
     font_id: Ref,
@@ -9,12 +57,61 @@ pub struct PdfFieldRenderer {
     font_name: Name<'static>,
     symbol_font_name: Name<'static>,
     next_ref: i32,
-    image_refs: HashMap<String, (Ref, u32, u32)>, // (ref, width, height)
+    image_refs: HashMap<String, EmbeddedImage>,
+    /// Graphics states by (opacity, multiply blending), named `Gs{index}` on every page
+    graphics_states: Vec<((f32, bool), Ref)>,
+    fonts: FontRegistry,
+    /// Text of `continue` fields left for continuation sheets
+    continuations: Vec<Continuation>,
+    /// `letter_spacing` of the text field being drawn, included in text widths
+    letter_spacing: f32,
     pub metadata: ProcessingMetadata,
 }
 
+/// Resources every overlay page lists, see `PdfFieldRenderer::shared_resources`.
+struct SharedResources {
+    fonts: Vec<(String, Ref)>,
+    graphics_states: Vec<Ref>,
+}
+
+impl SharedResources {
+    /// Write these plus the page's images.
+    fn write(&self, resources: &mut Resources, image_refs: &[Ref]) {
+        let mut fonts = resources.fonts();
+        for (name, font_ref) in &self.fonts {
+            fonts.pair(Name(name.as_bytes()), *font_ref);
+        }
+        fonts.finish();
+        
+        if !self.graphics_states.is_empty() {
+            let mut ext_g_states = resources.ext_g_states();
+            for (index, state_ref) in self.graphics_states.iter().enumerate() {
+                ext_g_states.pair(Name(format!("Gs{}", index).as_bytes()), *state_ref);
+            }
+            ext_g_states.finish();
+        }
+        
+        if !image_refs.is_empty() {
+            let mut xobjects = resources.x_objects();
+            for img_ref in image_refs {
+                let img_name = format!("Im{}", img_ref.get());
+                xobjects.pair(Name(img_name.as_bytes()), *img_ref);
+            }
+        }
+    }
+}
+
+/// What a `continue` field couldn't fit in its box.
+struct Continuation {
+    field: FieldData,
+    text: String,
+    font: FontKey,
+    page_number: u32,
+    page_size: (f32, f32),
+}
+
 impl PdfFieldRenderer {
-    pub fn new() -> Self {
+    pub fn new(fonts: FontCache) -> Self {
         Self {
             pdf: Pdf::new(),
             font_id: Ref::new(1),
@@ -23,17 +120,24 @@ impl PdfFieldRenderer {
             symbol_font_name: Name(b"F2"),
             next_ref: 3,
             image_refs: HashMap::new(),
+            graphics_states: Vec::new(),
+            fonts: FontRegistry::new(fonts),
+            continuations: Vec::new(),
+            letter_spacing: 0.0,
             metadata: ProcessingMetadata {
                 pages: Vec::new(),
                 fields_processed: 0,
                 fields_skipped: 0,
+                images: Vec::new(),
+                text_fits: Vec::new(),
+                continuations: Vec::new(),
                 warnings: Vec::new(),
                 errors: Vec::new(),
             },
         }
     }
 
-    pub async fn create_populated_form(
+    pub fn create_populated_form(
         mut self,
         fields: &[FieldData],
         pdf_document: &PdfDocument,
@@ -62,7 +166,7 @@ impl PdfFieldRenderer {
             
             // Track page metadata
             self.metadata.pages.push(PageMetadata {
-                page_number: page_num,
+                page_number: page_info.page_number,
                 width: page_info.width,
                 height: page_info.height,
                 fields_count: page_fields.len(),
@@ -78,13 +182,48 @@ impl PdfFieldRenderer {
             let mut page_image_refs = Vec::new();
 
             for field in page_fields {
+                self.begin_field_style(field, page_info, &mut content);
                 match &field.value {
                     FieldValue::Text(_) | FieldValue::Number(_) | FieldValue::Date(_) | FieldValue::Dropdown(_) => {
+                        let clip = matches!(field.text_overflow, Some(TextOverflow::Clip));
+                        if clip {
+                            let (pdf_x, pdf_y, width, height) = self.convert_coordinates(field, page_info);
+                            content.save_state();
+                            content.rect(pdf_x, pdf_y, width, height);
+                            content.clip_nonzero();
+                            content.end_path();
+                        }
                         content.begin_text();
                         self.render_text_with_fitting(field, page_info, &mut content);
                         content.end_text();
+                        self.letter_spacing = 0.0;
+                        if clip {
+                            content.restore_state();
+                        }
+                        self.metadata.fields_processed += 1;
+                    }
+                    FieldValue::TypedSignature(signature) => {
+                        self.render_typed_signature(field, page_info, signature, &mut content);
                         self.metadata.fields_processed += 1;
                     }
+                    FieldValue::Qrcode(_) | FieldValue::Barcode(_) => {
+                        match self.render_code(field, page_info, &mut content) {
+                            Ok(()) => self.metadata.fields_processed += 1,
+                            Err(e) => {
+                                self.metadata.errors.push(format!("Failed to draw code {}: {}", field.field_id, e));
+                                self.metadata.fields_skipped += 1;
+                            }
+                        }
+                    }
+                    FieldValue::Line(_) | FieldValue::Rect(_) | FieldValue::Ellipse(_) | FieldValue::Highlight(_) => {
+                        match self.render_shape(field, page_info, &mut content) {
+                            Ok(()) => self.metadata.fields_processed += 1,
+                            Err(e) => {
+                                self.metadata.errors.push(format!("Failed to draw shape {}: {}", field.field_id, e));
+                                self.metadata.fields_skipped += 1;
+                            }
+                        }
+                    }
                     FieldValue::Checkbox(_) => {
                         let field_ref = self.create_checkbox_field(field, page_info)?;
                         page_annotation_refs.push(field_ref);
@@ -97,21 +236,13 @@ impl PdfFieldRenderer {
                         all_annotation_refs.push(field_ref);
                         self.metadata.fields_processed += 1;
                     }
-                    FieldValue::Signature(img_source) | FieldValue::Image(img_source) => {
-                        let base64_img = match img_source {
-                            ImageSource::Base64(b64) => b64.clone(),
-                            ImageSource::Url(_) => {
-                                self.metadata.warnings.push(format!("Skipped URL image for field {}", field.field_id));
-                                self.metadata.fields_skipped += 1;
-                                continue;
-                            }
-                        };
-                        match self.decode_image(&base64_img) {
+                    FieldValue::Signature(img_source) | FieldValue::Image(img_source) => match img_source {
+                        ImageSource::Base64(base64_img) => match self.decode_image(base64_img) {
                             Ok(img_data) => {
-                                match self.embed_image(&img_data, &field.field_id) {
-                                    Ok((img_ref, _, _)) => {
-                                        page_image_refs.push(img_ref);
-                                        self.render_embedded_image(field, page_info, img_ref, &mut content);
+                                match self.embed_image(&img_data, field, page_info) {
+                                    Ok(image) => {
+                                        page_image_refs.push(image.id);
+                                        self.render_embedded_image(field, page_info, image, &mut content);
                                         self.metadata.fields_processed += 1;
                                     }
                                     Err(e) => {
@@ -124,11 +255,26 @@ impl PdfFieldRenderer {
                                 self.metadata.errors.push(format!("Failed to decode image {}: {}", field.field_id, e));
                                 self.metadata.fields_skipped += 1;
                             }
+                        },
+                        ImageSource::Url(_) => {
+                            self.metadata.warnings.push(format!("Skipped URL image for field {}", field.field_id));
+                            self.metadata.fields_skipped += 1;
                         }
-                    }
+                        ImageSource::Strokes(strokes) => {
+                            match self.render_strokes(field, page_info, strokes, &mut content) {
+                                Ok(()) => self.metadata.fields_processed += 1,
+                                Err(e) => {
+                                    self.metadata.errors.push(format!("Failed to draw signature {}: {}", field.field_id, e));
+                                    self.metadata.fields_skipped += 1;
+                                }
+                            }
+                        }
+                    },
                 }
+                self.end_field_style(field, page_info, &mut content);
             }
             
+            let shared = self.shared_resources();
             let mut page = self.pdf.page(page_id);
             page.media_box(Rect::new(0.0, 0.0, page_info.width, page_info.height))
                 .parent(page_tree_id)
@@ -138,34 +284,25 @@ impl PdfFieldRenderer {
                 page.annotations(page_annotation_refs.iter().copied());
             }
             
-            {
-                let mut resources = page.resources();
-                resources.fonts()
-                    .pair(self.font_name, self.font_id)
-                    .pair(self.symbol_font_name, self.symbol_font_id);
-                
-                if !page_image_refs.is_empty() {
-                    let mut xobjects = resources.x_objects();
-                    for img_ref in &page_image_refs {
-                        let img_name = format!("Im{}", img_ref.get());
-                        xobjects.pair(Name(img_name.as_bytes()), *img_ref);
-                    }
-                }
-            }
-            
+            shared.write(&mut page.resources(), &page_image_refs);
             page.finish();
             
             self.pdf.stream(content_id, &content.finish());
             page_ids.push(page_id);
         }
         
+        let sheet_ids = self.write_continuation_sheets(page_tree_id, pdf_document.pages.len() as u32);
+        page_ids.extend(sheet_ids);
+        
         let catalog_id = Ref::new(self.next_ref);
         self.next_ref += 1;
 
         self.pdf.type1_font(self.font_id)
-            .base_font(Name(b"Helvetica"));
+            .base_font(Name(b"Helvetica"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
         self.pdf.type1_font(self.symbol_font_id)
             .base_font(Name(b"ZapfDingbats"));
+        self.fonts.finish(&mut self.pdf, &mut self.next_ref)?;
         
         let page_count = page_ids.len() as i32;
         self.pdf.pages(page_tree_id).kids(page_ids).count(page_count);
@@ -180,7 +317,7 @@ impl PdfFieldRenderer {
         Ok((self.pdf.finish(), self.metadata))
     }
 
-    fn render_text_with_fitting(&self, field: &FieldData, page_info: &crate::types::PdfPageInfo, content: &mut Content) {
+    fn render_text_with_fitting(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, content: &mut Content) {
         let (pdf_x, pdf_y, width, height) = self.convert_coordinates(field, page_info);
         
         let text = match &field.value {
@@ -195,132 +332,839 @@ impl PdfFieldRenderer {
             return;
         }
 
-        content.set_fill_rgb(0.0, 0.0, 0.0);
+        let font = self.field_font(field);
+        // Line breaks are laid out, never drawn
+        if font == FontKey::Helvetica && !text.lines().all(|line| fonts::encode_win_ansi(line).1) {
+            self.metadata.warnings.push(format!(
+                "Field {} has characters Helvetica cannot encode; set \"font\" to an embedded font",
+                field.field_id
+            ));
+        }
+
+        if let Some(cells) = field.comb {
+            self.render_comb(field, page_info, &text, font, cells, content);
+            return;
+        }
+        
+        self.letter_spacing = field.letter_spacing.unwrap_or(0.0);
+        if self.letter_spacing != 0.0 {
+            content.set_char_spacing(self.letter_spacing);
+        }
+
+        if matches!(field.text_overflow, Some(TextOverflow::AutoFit | TextOverflow::Shrink)) {
+            self.render_auto_fit(field, page_info, &text, font, content);
+            return;
+        }
+        if matches!(field.text_overflow, Some(TextOverflow::Continue)) {
+            self.render_continued(field, page_info, &text, font, content);
+            return;
+        }
 
-        let base_font_size = field.font_size.unwrap_or(12.0).max(12.0);
+        let base_font_size = field.font_size.unwrap_or(12.0);
+        self.begin_styled_text(field, base_font_size, content);
         let reduced_font_size = base_font_size * 0.9;
         
-        let base_y = if height > base_font_size * 1.2 {
-            match field.vertical_alignment.as_deref() {
-                Some("middle") => pdf_y + (height - base_font_size) / 2.0,
-                Some("bottom") => pdf_y + height - base_font_size,
-                Some("baseline") => pdf_y + height - (base_font_size * 0.2),
-                _ => pdf_y,
-            }
-        } else {
-            pdf_y
-        };
+        let base_y = Self::single_line_y(field, pdf_y, height, base_font_size);
         
-        // Use srv-ocr's more accurate char width calculation (0.55 instead of 0.5)
-        let char_width = base_font_size * 0.55;
-        let text_width = text.len() as f32 * char_width;
+        let text_width = self.text_width(font, base_font_size, &text);
         
         // Check if cutoff mode is enabled
-        let use_cutoff = matches!(field.text_overflow.as_ref().unwrap_or(&crate::types::TextOverflow::Overflow), crate::types::TextOverflow::Cutoff);
+        let overflow = field.text_overflow.clone().unwrap_or_default();
+        let use_cutoff = matches!(overflow, TextOverflow::Cutoff | TextOverflow::Ellipsis);
+        // Explicit line breaks always lay out as multiple lines
+        let single_paragraph = !text.contains('\n');
         
-        if text_width <= width {
+        if single_paragraph && text_width <= width {
             let x_offset = match field.alignment.as_deref() {
                 Some("center") => (width - text_width) / 2.0,
                 Some("right") => width - text_width,
                 _ => 0.0,
             };
             
-            content.set_font(self.font_name, base_font_size);
-            content.next_line(pdf_x + x_offset, base_y);
-            content.show(Str(text.as_bytes()));
+            Self::place_text(field, content, pdf_x + x_offset, base_y);
+            self.show_text(content, font, base_font_size, &text);
             return;
         }
         
-        let reduced_char_width = reduced_font_size * 0.55;
-        let reduced_text_width = text.len() as f32 * reduced_char_width;
+        let reduced_text_width = self.text_width(font, reduced_font_size, &text);
         
-        if reduced_text_width <= width {
+        if single_paragraph && reduced_text_width <= width {
             let x_offset = match field.alignment.as_deref() {
                 Some("center") => (width - reduced_text_width) / 2.0,
                 Some("right") => width - reduced_text_width,
                 _ => 0.0,
             };
             
-            let reduced_y = if height > reduced_font_size * 1.2 {
-                match field.vertical_alignment.as_deref() {
-                    Some("middle") => pdf_y + (height - reduced_font_size) / 2.0,
-                    Some("bottom") => pdf_y + height - reduced_font_size,
-                    Some("baseline") => pdf_y + height - (reduced_font_size * 0.2),
-                    _ => pdf_y,
-                }
-            } else {
-                pdf_y
-            };
+            let reduced_y = Self::single_line_y(field, pdf_y, height, reduced_font_size);
             
-            content.set_font(self.font_name, reduced_font_size);
-            content.next_line(pdf_x + x_offset, reduced_y);
-            content.show(Str(text.as_bytes()));
+            Self::place_text(field, content, pdf_x + x_offset, reduced_y);
+            self.show_text(content, font, reduced_font_size, &text);
             return;
         }
         
         // Multi-line with word wrapping (srv-ocr approach)
-        let line_height = base_font_size * 1.2;
-        let max_lines = (height / line_height).floor() as usize;
+        let paragraphs = self.wrap_paragraphs(&text, width, font, base_font_size);
+        if self.lines_fit(field, &paragraphs, width, height, font, base_font_size) {
+            self.show_lines(field, self.convert_coordinates(field, page_info), content, font, base_font_size, &paragraphs);
+            return;
+        }
+        
+        if !single_paragraph || field.max_lines.is_some() {
+            let paragraphs = if use_cutoff {
+                self.cut_lines(field, paragraphs, width, height, font, base_font_size)
+            } else {
+                Self::limit_lines(field, paragraphs)
+            };
+            self.show_lines(field, self.convert_coordinates(field, page_info), content, font, base_font_size, &paragraphs);
+            return;
+        }
+        
+        // Fallback: render as-is (overflow, clip) or truncate (cutoff, ellipsis)
+        let final_text = match overflow {
+            TextOverflow::Cutoff => self.truncate_to_width(&text, width, font, base_font_size),
+            TextOverflow::Ellipsis => self.ellipsize(&text, width, font, base_font_size, false),
+            _ => text,
+        };
+        let final_width = self.text_width(font, base_font_size, &final_text);
+        let x_offset = match field.alignment.as_deref() {
+            Some("center") => (width - final_width) / 2.0,
+            Some("right") => width - final_width,
+            _ => 0.0,
+        };
         
-        if max_lines > 0 {
-            let lines = self.word_wrap(&text, width, base_font_size);
+        Self::place_text(field, content, pdf_x + x_offset, base_y);
+        self.show_text(content, font, base_font_size, &final_text);
+    }
+
+    /// Baseline of a single line of text in a box starting at `pdf_y`.
+    fn single_line_y(field: &FieldData, pdf_y: f32, height: f32, font_size: f32) -> f32 {
+        if height > font_size * 1.2 {
+            match field.vertical_alignment.as_deref() {
+                Some("middle") => pdf_y + (height - font_size) / 2.0,
+                Some("bottom") => pdf_y + height - font_size,
+                Some("baseline") => pdf_y + height - (font_size * 0.2),
+                _ => pdf_y,
+            }
+        } else {
+            pdf_y
+        }
+    }
+
+    /// Comb text: the box split into `cells` equal cells with one character
+    /// centered in each. Shorter text starts in the first cell, or is pushed
+    /// right or centered by `alignment`; characters past the last cell are dropped.
+    fn render_comb(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, text: &str, font: FontKey, cells: usize, content: &mut Content) {
+        let (pdf_x, pdf_y, width, height) = self.convert_coordinates(field, page_info);
+        if cells == 0 {
+            self.metadata.warnings.push(format!("Field {}: comb needs at least one cell", field.field_id));
+            return;
+        }
+        let chars: Vec<char> = text.chars().filter(|c| *c != '\n' && *c != '\r').collect();
+        if chars.len() > cells {
+            self.metadata.warnings.push(format!(
+                "Field {} has {} characters for {} comb cells, extra characters dropped",
+                field.field_id, chars.len(), cells
+            ));
+        }
+        
+        let font_size = field.font_size.unwrap_or(12.0);
+        self.begin_styled_text(field, font_size, content);
+        let cell = width / cells as f32;
+        let used = chars.len().min(cells);
+        let first_cell = match field.alignment.as_deref() {
+            Some("center") => (cells - used) / 2,
+            Some("right") => cells - used,
+            _ => 0,
+        };
+        let y = Self::single_line_y(field, pdf_y, height, font_size);
+        
+        // A text matrix per cell rather than one Tc for the run: glyphs differ
+        // in width, so a single spacing can't center each in its cell
+        for (i, c) in chars.iter().take(cells).enumerate() {
+            let glyph = c.to_string();
+            let x = pdf_x + (first_cell + i) as f32 * cell + (cell - self.text_width(font, font_size, &glyph)) / 2.0;
+            Self::place_text(field, content, x, y);
+            self.show_text(content, font, font_size, &glyph);
+        }
+    }
+
+    /// `auto_fit` and `shrink` text: the largest size between `min_font_size`
+    /// and `font_size` at which it fits the box, found by bisection. Text that
+    /// doesn't fit even at the minimum overflows.
+    fn render_auto_fit(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, text: &str, font: FontKey, content: &mut Content) {
+        let (_, _, width, height) = self.convert_coordinates(field, page_info);
+        let max_size = field.font_size.unwrap_or(12.0);
+        let min_size = field.min_font_size.unwrap_or(DEFAULT_MIN_FONT_SIZE).min(max_size);
+        
+        // `shrink` keeps each paragraph on one line
+        let wrap = !matches!(field.text_overflow, Some(TextOverflow::Shrink));
+        let layout = |size: f32| match wrap {
+            true => self.wrap_paragraphs(text, width, font, size),
+            false => text.lines().map(|line| vec![line.to_string()]).collect(),
+        };
+        let fit = |size: f32| {
+            let paragraphs = layout(size);
+            self.lines_fit(field, &paragraphs, width, height, font, size).then_some(paragraphs)
+        };
+        let (font_size, paragraphs, fits) = match Self::largest_fitting_size(min_size, max_size, fit) {
+            Some((font_size, paragraphs)) => (font_size, paragraphs, true),
+            None => (min_size, Self::limit_lines(field, layout(min_size)), false),
+        };
+        
+        if !fits {
+            self.metadata.warnings.push(format!(
+                "Field {} overflows its box even at {}pt",
+                field.field_id, min_size
+            ));
+        }
+        self.metadata.text_fits.push(TextFitMetadata {
+            field_id: field.field_id.clone(),
+            font_size,
+            lines: paragraphs.iter().map(Vec::len).sum(),
+            fits,
+        });
+        
+        self.begin_styled_text(field, font_size, content);
+        self.show_lines(field, self.convert_coordinates(field, page_info), content, font, font_size, &paragraphs);
+    }
+
+    /// The largest size between `min_size` and `max_size` for which `fit`
+    /// returns a layout, found by bisection, with that layout. `None` when
+    /// even `min_size` doesn't fit.
+    fn largest_fitting_size<T>(min_size: f32, max_size: f32, fit: impl Fn(f32) -> Option<T>) -> Option<(f32, T)> {
+        if let Some(layout) = fit(max_size) {
+            return Some((max_size, layout));
+        }
+        let mut best = fit(min_size)?;
+        let (mut low, mut high) = (min_size, max_size);
+        // Closer than this makes no visible difference
+        while high - low > 0.05 {
+            let mid = (low + high) / 2.0;
+            match fit(mid) {
+                Some(layout) => {
+                    low = mid;
+                    best = layout;
+                }
+                None => high = mid,
+            }
+        }
+        Some(((low * 100.0).floor() / 100.0, best))
+    }
+
+    /// `continue` text: as many lines as fit the box, the last of them a
+    /// marker, with the rest kept for a continuation sheet.
+    fn render_continued(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, text: &str, font: FontKey, content: &mut Content) {
+        let area = self.convert_coordinates(field, page_info);
+        let (_, _, width, height) = area;
+        let font_size = field.font_size.unwrap_or(12.0);
+        self.begin_styled_text(field, font_size, content);
+        
+        let paragraphs = self.wrap_paragraphs(text, width, font, font_size);
+        if self.lines_fit(field, &paragraphs, width, height, font, font_size) {
+            self.show_lines(field, area, content, font, font_size, &paragraphs);
+            return;
+        }
+        
+        let fitting = Self::fitting_lines(field, &paragraphs, height, font_size);
+        let (mut shown, rest) = Self::split_lines(paragraphs, fitting.saturating_sub(1));
+        match shown.last_mut() {
+            Some(lines) => lines.push(CONTINUATION_MARKER.to_string()),
+            None => shown.push(vec![CONTINUATION_MARKER.to_string()]),
+        }
+        self.show_lines(field, area, content, font, font_size, &shown);
+        
+        self.continuations.push(Continuation {
+            // The sheet takes as many lines as it needs
+            field: FieldData { max_lines: None, ..field.clone() },
+            text: rest.iter().map(|lines| lines.join(" ")).collect::<Vec<_>>().join("\n"),
+            font,
+            page_number: page_info.page_number,
+            page_size: (page_info.width, page_info.height),
+        });
+    }
+
+    /// Pages with the text `continue` fields couldn't fit, each field on
+    /// pages of its own the size of its template page. They follow the pages
+    /// stamped onto the template and are appended to the filled PDF.
+    fn write_continuation_sheets(&mut self, page_tree_id: Ref, first_page: u32) -> Vec<Ref> {
+        let mut page_ids = Vec::new();
+        for continuation in std::mem::take(&mut self.continuations) {
+            let Continuation { field, text, font, page_number, page_size: (page_width, page_height) } = continuation;
+            let font_size = field.font_size.unwrap_or(12.0);
+            let width = page_width - 2.0 * SHEET_MARGIN;
+            let body_top = page_height - SHEET_MARGIN - SHEET_HEADING_HEIGHT;
+            let label = field.label.as_deref().unwrap_or(&field.field_id);
+            let title = format!("{} (continued)", label);
+            let title_font = if fonts::encode_win_ansi(&title).1 { FontKey::Helvetica } else { font };
+            let sheet_start = first_page + page_ids.len() as u32;
             
-            if lines.len() <= max_lines {
-                content.set_font(self.font_name, base_font_size);
+            self.letter_spacing = field.letter_spacing.unwrap_or(0.0);
+            let mut rest = self.wrap_paragraphs(&text, width, font, font_size);
+            while !rest.is_empty() {
+                let fitting = Self::fitting_lines(&field, &rest, body_top - SHEET_MARGIN, font_size).max(1);
+                let (lines, more) = Self::split_lines(rest, fitting);
+                rest = more;
                 
-                let total_text_height = lines.len() as f32 * line_height;
-                let first_line_y = if height > total_text_height {
-                    match field.vertical_alignment.as_deref() {
-                        Some("middle") => pdf_y + (height - total_text_height) / 2.0,
-                        Some("bottom") | Some("baseline") => pdf_y + height - total_text_height,
-                        _ => pdf_y,
-                    }
+                let mut content = Content::new();
+                content.begin_text();
+                content.set_text_matrix([1.0, 0.0, 0.0, 1.0, SHEET_MARGIN, page_height - SHEET_MARGIN - SHEET_TITLE_SIZE]);
+                self.show_text(&mut content, title_font, SHEET_TITLE_SIZE, &title);
+                content.set_text_matrix([1.0, 0.0, 0.0, 1.0, SHEET_MARGIN, page_height - SHEET_MARGIN - SHEET_TITLE_SIZE - 16.0]);
+                self.show_text(&mut content, FontKey::Helvetica, 9.0, &format!("Continued from page {}", page_number + 1));
+                
+                self.begin_styled_text(&field, font_size, &mut content);
+                if self.letter_spacing != 0.0 {
+                    content.set_char_spacing(self.letter_spacing);
+                }
+                let block_height = Self::block_height(&field, &lines, font_size);
+                let area = (SHEET_MARGIN, body_top - block_height, width, block_height);
+                self.show_lines(&field, area, &mut content, font, font_size, &lines);
+                content.end_text();
+                
+                let content_id = Ref::new(self.next_ref);
+                let page_id = Ref::new(self.next_ref + 1);
+                self.next_ref += 2;
+                let shared = self.shared_resources();
+                let mut page = self.pdf.page(page_id);
+                page.media_box(Rect::new(0.0, 0.0, page_width, page_height))
+                    .parent(page_tree_id)
+                    .contents(content_id);
+                shared.write(&mut page.resources(), &[]);
+                page.finish();
+                self.pdf.stream(content_id, &content.finish());
+                page_ids.push(page_id);
+            }
+            self.letter_spacing = 0.0;
+            
+            self.metadata.continuations.push(ContinuationMetadata {
+                field_id: field.field_id.clone(),
+                first_page: sheet_start,
+                pages: first_page + page_ids.len() as u32 - sheet_start,
+            });
+        }
+        page_ids
+    }
+
+    /// Fonts and graphics states, which every page lists in its resources.
+    fn shared_resources(&self) -> SharedResources {
+        let mut fonts = vec![
+            (String::from_utf8_lossy(self.font_name.0).into_owned(), self.font_id),
+            (String::from_utf8_lossy(self.symbol_font_name.0).into_owned(), self.symbol_font_id),
+        ];
+        fonts.extend(self.fonts.resources());
+        SharedResources {
+            fonts,
+            graphics_states: self.graphics_states.iter().map(|(_, state_ref)| *state_ref).collect(),
+        }
+    }
+
+    /// Each `\n`-separated paragraph of `text`, word-wrapped. A single
+    /// paragraph that fits on one line is kept whole.
+    fn wrap_paragraphs(&self, text: &str, width: f32, font: FontKey, font_size: f32) -> Vec<Vec<String>> {
+        let paragraphs: Vec<&str> = text.lines().collect();
+        if let [line] = paragraphs[..] {
+            if self.text_width(font, font_size, line) <= width {
+                return vec![vec![line.to_string()]];
+            }
+        }
+        paragraphs.iter()
+            .map(|paragraph| match paragraph.trim() {
+                "" => vec![String::new()],
+                _ => self.word_wrap(paragraph, width, font, font_size),
+            })
+            .collect()
+    }
+
+    /// Baseline-to-baseline distance and extra space after each paragraph.
+    fn line_spacing(field: &FieldData, font_size: f32) -> (f32, f32) {
+        let line_height = font_size * field.line_height.unwrap_or(1.2);
+        (line_height, field.paragraph_spacing.unwrap_or(0.0))
+    }
+
+    fn block_height(field: &FieldData, paragraphs: &[Vec<String>], font_size: f32) -> f32 {
+        let (line_height, paragraph_spacing) = Self::line_spacing(field, font_size);
+        let lines: usize = paragraphs.iter().map(Vec::len).sum();
+        lines as f32 * line_height + paragraphs.len().saturating_sub(1) as f32 * paragraph_spacing
+    }
+
+    /// Whether wrapped lines fit the box width and, unless `max_lines` is
+    /// set, its height. A single line only needs room for the font size.
+    fn lines_fit(&self, field: &FieldData, paragraphs: &[Vec<String>], width: f32, height: f32, font: FontKey, font_size: f32) -> bool {
+        let lines: usize = paragraphs.iter().map(Vec::len).sum();
+        let fits_height = match field.max_lines {
+            Some(max_lines) => lines <= max_lines,
+            None if lines == 1 => font_size <= height,
+            None => Self::block_height(field, paragraphs, font_size) <= height,
+        };
+        fits_height && paragraphs.iter().flatten().all(|line| self.text_width(font, font_size, line) <= width)
+    }
+
+    /// Drop the lines past `max_lines`, or past the box height without it,
+    /// and cut any line wider than the box. At least one line is kept. In
+    /// `ellipsis` mode cut lines end in "…", as does the last one if any are dropped.
+    fn cut_lines(&self, field: &FieldData, paragraphs: Vec<Vec<String>>, width: f32, height: f32, font: FontKey, font_size: f32) -> Vec<Vec<String>> {
+        let ellipsis = matches!(field.text_overflow, Some(TextOverflow::Ellipsis));
+        let fitting = Self::fitting_lines(field, &paragraphs, height, font_size).max(1);
+        let (mut kept, rest) = Self::split_lines(paragraphs, fitting);
+        let dropped = !rest.is_empty();
+        
+        let last = (kept.len() - 1, kept.last().map_or(0, |lines| lines.len() - 1));
+        for (i, lines) in kept.iter_mut().enumerate() {
+            for (j, line) in lines.iter_mut().enumerate() {
+                *line = if !ellipsis {
+                    self.truncate_to_width(line, width, font, font_size)
                 } else {
-                    pdf_y
+                    self.ellipsize(line, width, font, font_size, dropped && (i, j) == last)
                 };
-                
-                for (i, line) in lines.iter().enumerate() {
-                    let line_width = line.len() as f32 * char_width;
-                    let x_offset = match field.alignment.as_deref() {
-                        Some("center") => (width - line_width) / 2.0,
-                        Some("right") => width - line_width,
-                        _ => 0.0,
-                    };
-                    
-                    let y_offset = first_line_y + (i as f32 * line_height);
-                    content.next_line(pdf_x + x_offset, y_offset);
-                    content.show(Str(line.as_bytes()));
+            }
+        }
+        kept
+    }
+
+    /// The first `max_lines` lines, or all of them without it.
+    fn limit_lines(field: &FieldData, paragraphs: Vec<Vec<String>>) -> Vec<Vec<String>> {
+        match field.max_lines {
+            Some(max_lines) => Self::split_lines(paragraphs, max_lines.max(1)).0,
+            None => paragraphs,
+        }
+    }
+
+    /// How many of the lines fit within `max_lines`, or the height without it.
+    fn fitting_lines(field: &FieldData, paragraphs: &[Vec<String>], height: f32, font_size: f32) -> usize {
+        let lines = paragraphs.iter().map(Vec::len).sum();
+        if let Some(max_lines) = field.max_lines {
+            return max_lines.min(lines);
+        }
+        
+        let (line_height, paragraph_spacing) = Self::line_spacing(field, font_size);
+        let (mut count, mut used) = (0, 0.0);
+        for (i, paragraph) in paragraphs.iter().enumerate() {
+            for j in 0..paragraph.len() {
+                let advance = if i > 0 && j == 0 { paragraph_spacing + line_height } else { line_height };
+                if used + advance > height {
+                    return count;
                 }
-                return;
+                used += advance;
+                count += 1;
             }
         }
+        count
+    }
+
+    /// Paragraphs of the first `count` lines, and of the rest. A paragraph
+    /// split between the two ends one and starts the other.
+    fn split_lines(paragraphs: Vec<Vec<String>>, count: usize) -> (Vec<Vec<String>>, Vec<Vec<String>>) {
+        let (mut head, mut tail) = (Vec::new(), Vec::new());
+        let mut left = count;
+        for mut lines in paragraphs {
+            if left >= lines.len() {
+                left -= lines.len();
+                head.push(lines);
+            } else if left > 0 {
+                tail.push(lines.split_off(left));
+                head.push(lines);
+                left = 0;
+            } else {
+                tail.push(lines);
+            }
+        }
+        (head, tail)
+    }
+
+    /// `text` cut to `width` with "…" in place of what's dropped. `always`
+    /// adds it even when nothing is, for text that goes on past this line.
+    fn ellipsize(&self, text: &str, width: f32, font: FontKey, font_size: f32, always: bool) -> String {
+        if !always && self.text_width(font, font_size, text) <= width {
+            return text.to_string();
+        }
+        let room = width - self.text_width(font, font_size, ELLIPSIS);
+        format!("{}{}", self.truncate_to_width(text, room, font, font_size).trim_end(), ELLIPSIS)
+    }
+
+    /// Draw paragraphs of lines top to bottom, aligned as a block. A block
+    /// taller than the box starts at its top and runs out below.
+    fn show_lines(&mut self, field: &FieldData, area: (f32, f32, f32, f32), content: &mut Content, font: FontKey, font_size: f32, paragraphs: &[Vec<String>]) {
+        let (pdf_x, pdf_y, width, height) = area;
+        let (line_height, paragraph_spacing) = Self::line_spacing(field, font_size);
+        let total_text_height = Self::block_height(field, paragraphs, font_size);
+        // Baseline of the last line
+        let last_line_y = if height > total_text_height {
+            match field.vertical_alignment.as_deref() {
+                Some("middle") => pdf_y + (height - total_text_height) / 2.0,
+                Some("bottom") | Some("baseline") => pdf_y + height - total_text_height,
+                _ => pdf_y,
+            }
+        } else {
+            pdf_y + height - total_text_height
+        };
+        
+        // First line on top
+        let mut y = last_line_y + total_text_height - line_height;
+        for (i, paragraph) in paragraphs.iter().enumerate() {
+            if i > 0 {
+                y -= paragraph_spacing;
+            }
+            for (j, line) in paragraph.iter().enumerate() {
+                let line_width = self.text_width(font, font_size, line);
+                let x_offset = match field.alignment.as_deref() {
+                    Some("center") => (width - line_width) / 2.0,
+                    Some("right") => width - line_width,
+                    _ => 0.0,
+                };
+                // Justified lines share the leftover width between their
+                // spaces, except each paragraph's last line
+                let spaces = line.matches(' ').count();
+                let word_spacing = match field.alignment.as_deref() {
+                    Some("justify") if j + 1 < paragraph.len() && spaces > 0 && line_width < width => {
+                        (width - line_width) / spaces as f32
+                    }
+                    _ => 0.0,
+                };
+                
+                Self::place_text(field, content, pdf_x + x_offset, y);
+                self.show_spaced_text(content, font, font_size, line, word_spacing);
+                y -= line_height;
+            }
+        }
+    }
+
+    /// Draw a typed name as large as fits the box, above an optional
+    /// signature line and "Digitally signed by" caption.
+    fn render_typed_signature(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, signature: &TypedSignature, content: &mut Content) {
+        let (pdf_x, pdf_y, width, height) = self.convert_coordinates(field, page_info);
+        if signature.name.trim().is_empty() {
+            return;
+        }
+        
+        if field.font.is_none() {
+            self.metadata.warnings.push(format!(
+                "Typed signature {} has no \"font\"; set it to a script font, using Helvetica",
+                field.field_id
+            ));
+        }
+        let font = self.field_font(field);
+        if font == FontKey::Helvetica && !fonts::encode_win_ansi(&signature.name).1 {
+            self.metadata.warnings.push(format!(
+                "Field {} has characters Helvetica cannot encode; set \"font\" to an embedded font",
+                field.field_id
+            ));
+        }
+        
+        content.save_state();
+        let color = self.style_color(field, field.color.as_deref()).unwrap_or(Color::BLACK);
+        color.set_fill(content);
+        color.set_stroke(content);
         
-        // Fallback: render as-is (overflow) or truncate (cutoff)
-        let final_text = if use_cutoff {
-            // Calculate maximum characters that fit within width
-            // For Helvetica, use 0.5 multiplier (more aggressive fitting)
-            let cutoff_char_width = base_font_size * 0.5;
-            let max_chars = (width / cutoff_char_width).floor() as usize;
+        // Caption along the bottom, in Helvetica unless the name needs the signature font
+        let mut bottom = pdf_y;
+        if signature.caption {
+            let date = signature.signed_on.clone()
+                .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+            let caption = format!("Digitally signed by {} on {}", signature.name.trim(), date);
+            let caption_font = if fonts::encode_win_ansi(&caption).1 { FontKey::Helvetica } else { font };
+            let caption_width = self.text_width(caption_font, 1.0, &caption);
+            let caption_size = CAPTION_FONT_SIZE.min(height * 0.25).min(width / caption_width);
             
-            if text.len() > max_chars {
-                text.chars().take(max_chars).collect()
-            } else {
-                text
+            content.begin_text();
+            content.next_line(pdf_x, pdf_y + caption_size * 0.3);
+            self.show_text(content, caption_font, caption_size, &caption);
+            content.end_text();
+            bottom += caption_size * 1.4;
+        }
+        
+        // Largest size whose line, descenders included, fits the remaining box
+        let name = signature.name.trim();
+        let line_height = (pdf_y + height - bottom) / 1.3;
+        let max_size = field.font_size.unwrap_or(line_height).min(line_height);
+        let min_size = field.min_font_size.unwrap_or(DEFAULT_MIN_FONT_SIZE).min(max_size);
+        let line = [vec![name.to_string()]];
+        let fit = |size: f32| self.lines_fit(field, &line, width, line_height, font, size).then_some(());
+        let font_size = match Self::largest_fitting_size(min_size, max_size, fit) {
+            Some((font_size, ())) => font_size,
+            None => {
+                self.metadata.warnings.push(format!(
+                    "Typed signature {} overflows its box even at {}pt",
+                    field.field_id, min_size
+                ));
+                min_size
+            }
+        };
+        let baseline = bottom + font_size * 0.35;
+        
+        if signature.underline {
+            let line_y = bottom + font_size * 0.1;
+            content.set_line_width((font_size * 0.03).clamp(0.5, 1.0));
+            content.move_to(pdf_x, line_y);
+            content.line_to(pdf_x + width, line_y);
+            content.stroke();
+        }
+        
+        let text_width = self.text_width(font, font_size, name);
+        let x_offset = match field.alignment.as_deref() {
+            Some("center") => (width - text_width) / 2.0,
+            Some("right") => width - text_width,
+            _ => 0.0,
+        };
+        content.begin_text();
+        content.next_line(pdf_x + x_offset, baseline);
+        self.show_text(content, font, font_size, name);
+        content.end_text();
+        content.restore_state();
+    }
+
+    /// Draw a QR code or barcode as filled rectangles. QR codes keep square
+    /// modules and are placed by `alignment` and `vertical_alignment` as text
+    /// is; barcodes stretch across the box. Human-readable text goes
+    /// underneath in Helvetica.
+    fn render_code(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, content: &mut Content) -> anyhow::Result<()> {
+        let (rows, quiet_zone, text) = match &field.value {
+            FieldValue::Qrcode(code) => {
+                (barcode::qr_code(code)?, code.quiet_zone.unwrap_or(4), code.human_readable.then(|| code.data.clone()))
             }
+            FieldValue::Barcode(code) => {
+                let (bars, text) = match code.symbology {
+                    Symbology::Code128 => (barcode::code128(&code.data)?, code.data.clone()),
+                    Symbology::Ean13 => barcode::ean13(&code.data)?,
+                };
+                (vec![bars], code.quiet_zone.unwrap_or(10), code.human_readable.then_some(text))
+            }
+            _ => return Ok(()),
+        };
+        let is_qr = matches!(field.value, FieldValue::Qrcode(_));
+        let (pdf_x, pdf_y, width, height) = self.convert_coordinates(field, page_info);
+        
+        let text_size = text.as_deref().map(|text| {
+            field.font_size.unwrap_or(10.0)
+                .min(height * 0.25)
+                .min(width / metrics::helvetica_width(text, 1.0))
+        });
+        let text_height = text_size.map(|size| size * 1.2).unwrap_or(0.0);
+        
+        // Quiet zones go on every side of a QR code but only beside barcode bars
+        let columns = (rows[0].len() + 2 * quiet_zone as usize) as f32;
+        let (module_width, module_height, symbol_width, symbol_height) = if is_qr {
+            let module = (width / columns).min((height - text_height) / columns);
+            (module, module, module * columns, module * columns)
         } else {
-            text
+            let bar_height = height - text_height;
+            (width / columns, bar_height, width, bar_height)
+        };
+        
+        let x_offset = match field.alignment.as_deref() {
+            Some("center") => (width - symbol_width) / 2.0,
+            Some("right") => width - symbol_width,
+            _ => 0.0,
         };
+        let block_height = symbol_height + text_height;
+        // The same placement as a text block of this height
+        let top = pdf_y + height - match field.vertical_alignment.as_deref() {
+            Some("middle") => (height - block_height) / 2.0,
+            Some("bottom") | Some("baseline") => 0.0,
+            _ => height - block_height,
+        };
+        
+        content.save_state();
+        self.style_color(field, field.color.as_deref()).unwrap_or(Color::BLACK).set_fill(content);
+        let quiet_x = quiet_zone as f32 * module_width;
+        let quiet_y = if is_qr { quiet_zone as f32 * module_height } else { 0.0 };
+        barcode::draw_modules(content, &rows, pdf_x + x_offset + quiet_x, top - quiet_y, module_width, module_height);
         
-        content.set_font(self.font_name, base_font_size);
-        content.next_line(pdf_x, pdf_y);
-        content.show(Str(final_text.as_bytes()));
+        if let (Some(text), Some(size)) = (text, text_size) {
+            // Centered under the symbol, but never past the box when it is wider
+            let text_width = metrics::helvetica_width(&text, size);
+            let text_x = (x_offset + (symbol_width - text_width) / 2.0).clamp(0.0, (width - text_width).max(0.0));
+            content.begin_text();
+            content.next_line(pdf_x + text_x, top - symbol_height - size);
+            self.show_text(content, FontKey::Helvetica, size, &text);
+            content.end_text();
+        }
+        content.restore_state();
+        Ok(())
     }
 
-    fn word_wrap(&self, text: &str, width: f32, font_size: f32) -> Vec<String> {
-        let char_width = font_size * 0.55;
-        let max_chars_per_line = (width / char_width) as usize;
+    /// Draw a line, rectangle, ellipse or highlight over the field box.
+    fn render_shape(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, content: &mut Content) -> anyhow::Result<()> {
+        let (pdf_x, pdf_y, width, height) = self.convert_coordinates(field, page_info);
+        match &field.value {
+            FieldValue::Line(shape) => shapes::line(content, shape, pdf_x, pdf_y, width, height),
+            FieldValue::Rect(shape) => shapes::rect(content, shape, pdf_x, pdf_y, width, height),
+            FieldValue::Ellipse(shape) => shapes::ellipse(content, shape, pdf_x, pdf_y, width, height),
+            FieldValue::Highlight(highlight) => {
+                let color = Color::parse(highlight.color.as_deref().unwrap_or("#ffeb3b"))?;
+                let state = self.graphics_state(highlight.opacity.unwrap_or(0.35), true);
+                content.save_state();
+                content.set_parameters(Name(format!("Gs{}", state).as_bytes()));
+                color.set_fill(content);
+                content.rect(pdf_x, pdf_y, width, height);
+                content.fill_nonzero();
+                content.restore_state();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Index of the graphics state with this constant opacity, written on
+    /// first use. Highlights blend with multiply, which keeps the text
+    /// underneath as dark as before.
+    fn graphics_state(&mut self, opacity: f32, multiply: bool) -> usize {
+        let key = (opacity.clamp(0.0, 1.0), multiply);
+        if let Some(index) = self.graphics_states.iter().position(|(k, _)| *k == key) {
+            return index;
+        }
+        let state_ref = Ref::new(self.next_ref);
+        self.next_ref += 1;
+        let mut state = self.pdf.ext_graphics(state_ref);
+        state.non_stroking_alpha(key.0).stroking_alpha(key.0);
+        if multiply {
+            state.blend_mode(pdf_writer::types::BlendMode::Multiply);
+        }
+        state.finish();
+        self.graphics_states.push((key, state_ref));
+        self.graphics_states.len() - 1
+    }
+
+    /// Open a field's styled group: its opacity, then its background.
+    fn begin_field_style(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, content: &mut Content) {
+        let (pdf_x, pdf_y, width, height) = self.convert_coordinates(field, page_info);
+        content.save_state();
+        if let Some(opacity) = field.opacity.filter(|o| *o < 1.0) {
+            let state = self.graphics_state(opacity, false);
+            content.set_parameters(Name(format!("Gs{}", state).as_bytes()));
+        }
+        if let Some(background) = self.style_color(field, field.background.as_deref()) {
+            background.set_fill(content);
+            content.rect(pdf_x, pdf_y, width, height);
+            content.fill_nonzero();
+        }
+    }
+
+    /// Draw a field's border over what it drew and close the styled group.
+    fn end_field_style(&mut self, field: &FieldData, page_info: &crate::types::PdfPageInfo, content: &mut Content) {
+        let (pdf_x, pdf_y, width, height) = self.convert_coordinates(field, page_info);
+        let border_color = self.style_color(field, field.border_color.as_deref());
+        let border_width = field.border_width.or(border_color.map(|_| 1.0)).unwrap_or(0.0);
+        if border_width > 0.0 {
+            let inset = border_width / 2.0;
+            border_color.unwrap_or(Color::BLACK).set_stroke(content);
+            content.set_line_width(border_width);
+            content.rect(pdf_x + inset, pdf_y + inset, (width - border_width).max(0.0), (height - border_width).max(0.0));
+            content.stroke();
+        }
+        content.restore_state();
+    }
+
+    /// A style color of the field; invalid ones are reported and left out.
+    fn style_color(&mut self, field: &FieldData, color: Option<&str>) -> Option<Color> {
+        match Color::parse(color?) {
+            Ok(color) => Some(color),
+            Err(e) => {
+                self.metadata.warnings.push(format!("Field {}: {}, ignored", field.field_id, e));
+                None
+            }
+        }
+    }
+
+    /// Set the check mark color and opacity in a checkbox or radio appearance
+    /// stream, which the page's graphics state doesn't reach. Returns the
+    /// graphics state the stream uses, if any.
+    fn begin_mark_style(&mut self, field: &FieldData, content: &mut Content) -> Option<usize> {
+        let state = field.opacity.filter(|o| *o < 1.0).map(|opacity| self.graphics_state(opacity, false));
+        if let Some(index) = state {
+            content.set_parameters(Name(format!("Gs{}", index).as_bytes()));
+        }
+        if let Some(color) = self.style_color(field, field.color.as_deref()) {
+            color.set_fill(content);
+        }
+        state
+    }
+
+    /// Set the text color and synthesized bold for a field's text object.
+    fn begin_styled_text(&mut self, field: &FieldData, font_size: f32, content: &mut Content) {
+        let color = self.style_color(field, field.color.as_deref()).unwrap_or(Color::BLACK);
+        color.set_fill(content);
+        if field.bold == Some(true) {
+            color.set_stroke(content);
+            content.set_line_width(font_size * BOLD_STROKE);
+            content.set_text_rendering_mode(TextRenderingMode::FillStroke);
+        }
+    }
+
+    /// Start a line of text at `(x, y)`, slanted for synthesized italic.
+    fn place_text(field: &FieldData, content: &mut Content, x: f32, y: f32) {
+        let skew = if field.italic == Some(true) { ITALIC_SKEW } else { 0.0 };
+        content.set_text_matrix([1.0, 0.0, skew, 1.0, x, y]);
+    }
+
+    /// Font for a field: its embedded `font` if it resolves, otherwise Helvetica.
+    fn field_font(&mut self, field: &FieldData) -> FontKey {
+        let Some(name) = field.font.as_deref() else {
+            return FontKey::Helvetica;
+        };
         
-        if max_chars_per_line == 0 {
+        match self.fonts.resolve(name, &mut self.next_ref) {
+            Ok(key) => key,
+            Err(e) => {
+                self.metadata.warnings.push(format!("Field {}: {}, using Helvetica", field.field_id, e));
+                FontKey::Helvetica
+            }
+        }
+    }
+
+    fn show_text(&mut self, content: &mut Content, font: FontKey, font_size: f32, text: &str) {
+        self.show_spaced_text(content, font, font_size, text, 0.0);
+    }
+
+    /// Show `text` with `word_spacing` extra points after each space.
+    fn show_spaced_text(&mut self, content: &mut Content, font: FontKey, font_size: f32, text: &str, word_spacing: f32) {
+        match font {
+            FontKey::Helvetica => {
+                content.set_font(self.font_name, font_size);
+                if word_spacing != 0.0 {
+                    content.set_word_spacing(word_spacing);
+                }
+                content.show(Str(&fonts::encode_win_ansi(text).0));
+                if word_spacing != 0.0 {
+                    content.set_word_spacing(0.0);
+                }
+            }
+            FontKey::Embedded(index) => {
+                self.fonts.show_text(content, index, font_size, text, word_spacing);
+            }
+        }
+    }
+
+    /// Width of `text` as it will print, in points.
+    fn text_width(&self, font: FontKey, font_size: f32, text: &str) -> f32 {
+        let width = match font {
+            FontKey::Helvetica => metrics::helvetica_width(text, font_size),
+            FontKey::Embedded(index) => self.fonts.text_width(index, font_size, text),
+        };
+        // Spacing after the last character doesn't show
+        width + self.letter_spacing * text.chars().count().saturating_sub(1) as f32
+    }
+
+    /// Longest prefix of `text` (on character boundaries) that fits in `width`.
+    fn truncate_to_width(&self, text: &str, width: f32, font: FontKey, font_size: f32) -> String {
+        let boundaries: Vec<usize> = text.char_indices()
+            .map(|(i, _)| i)
+            .skip(1)
+            .chain(std::iter::once(text.len()))
+            .collect();
+
+        // Widths only grow with more characters, so binary search the cut point
+        let fitting = boundaries.partition_point(|&end| {
+            self.text_width(font, font_size, &text[..end]) <= width
+        });
+
+        match fitting {
+            0 => String::new(),
+            n => text[..boundaries[n - 1]].to_string(),
+        }
+    }
+
+    fn word_wrap(&self, text: &str, width: f32, font: FontKey, font_size: f32) -> Vec<String> {
+        if self.text_width(font, font_size, "M") > width {
             return vec![text.to_string()];
         }
         
@@ -335,7 +1179,7 @@ impl PdfFieldRenderer {
                 format!("{} {}", current_line, word)
             };
             
-            if test_line.len() <= max_chars_per_line {
+            if self.text_width(font, font_size, &test_line) <= width {
                 current_line = test_line;
             } else {
                 if !current_line.is_empty() {
@@ -368,6 +1212,7 @@ impl PdfFieldRenderer {
         let bbox = Rect::new(0.0, 0.0, width, height);
 
         let mut content = Content::new();
+        let state = self.begin_mark_style(field, &mut content);
         content.begin_text();
         content.set_font(self.symbol_font_name, 14.0);
         content.show(Str(b"4")); // Checkmark
@@ -376,7 +1221,12 @@ impl PdfFieldRenderer {
         let content_data = content.finish();
         let mut on_appearance = self.pdf.form_xobject(on_appearance_id, &content_data);
         on_appearance.bbox(bbox);
-        on_appearance.resources().fonts().pair(self.symbol_font_name, self.symbol_font_id);
+        let mut resources = on_appearance.resources();
+        resources.fonts().pair(self.symbol_font_name, self.symbol_font_id);
+        if let Some(index) = state {
+            resources.ext_g_states().pair(Name(format!("Gs{}", index).as_bytes()), self.graphics_states[index].1);
+        }
+        resources.finish();
         on_appearance.finish();
 
         self.pdf.form_xobject(off_appearance_id, &Content::new().finish()).bbox(bbox);
@@ -414,6 +1264,7 @@ impl PdfFieldRenderer {
         let bbox = Rect::new(0.0, 0.0, width, height);
 
         let mut content = Content::new();
+        let state = self.begin_mark_style(field, &mut content);
         content.begin_text();
         content.set_font(self.symbol_font_name, 14.0);
         content.show(Str(b"l")); // Filled circle
@@ -422,7 +1273,12 @@ impl PdfFieldRenderer {
         let content_data = content.finish();
         let mut on_appearance = self.pdf.form_xobject(on_appearance_id, &content_data);
         on_appearance.bbox(bbox);
-        on_appearance.resources().fonts().pair(self.symbol_font_name, self.symbol_font_id);
+        let mut resources = on_appearance.resources();
+        resources.fonts().pair(self.symbol_font_name, self.symbol_font_id);
+        if let Some(index) = state {
+            resources.ext_g_states().pair(Name(format!("Gs{}", index).as_bytes()), self.graphics_states[index].1);
+        }
+        resources.finish();
         on_appearance.finish();
 
         self.pdf.form_xobject(off_appearance_id, &Content::new().finish()).bbox(bbox);
@@ -454,41 +1310,178 @@ impl PdfFieldRenderer {
         Ok(STANDARD.decode(base64_str)?)
     }
 
-    fn embed_image(&mut self, img_data: &[u8], field_id: &str) -> anyhow::Result<(Ref, u32, u32)> {
-        if let Some(&existing) = self.image_refs.get(field_id) {
+    fn embed_image(&mut self, img_data: &[u8], field: &FieldData, page_info: &crate::types::PdfPageInfo) -> anyhow::Result<EmbeddedImage> {
+        if let Some(&existing) = self.image_refs.get(&field.field_id) {
             return Ok(existing);
         }
+        if svg::is_svg(img_data) {
+            return self.embed_svg(img_data, field);
+        }
+        
+        let (original_width, original_height) = images::dimensions(img_data)?;
+        let orientation = images::orientation(img_data);
         
-        let img = image::load_from_memory(img_data)?;
-        let rgb_img = img.to_rgb8();
-        let (width, height) = rgb_img.dimensions();
+        // Never embed more pixels than max_dpi needs at the placed size
+        let max_size = field.max_dpi.filter(|dpi| *dpi > 0.0).map(|dpi| {
+            let (_, scale) = self.image_matrix(field, page_info, original_width as f32, original_height as f32, orientation);
+            let pixels = |size: u32| (size as f32 * scale / 72.0 * dpi).ceil() as u32;
+            (pixels(original_width), pixels(original_height))
+        });
+        
+        let encoded = EncodedImage::encode(img_data, max_size, field.jpeg_quality)?;
+        self.metadata.images.push(ImageMetadata {
+            field_id: field.field_id.clone(),
+            original_width,
+            original_height,
+            original_bytes: img_data.len(),
+            width: encoded.width,
+            height: encoded.height,
+            bytes: encoded.byte_size(),
+            resampled: (encoded.width, encoded.height) != (original_width, original_height),
+        });
         
         let image_id = Ref::new(self.next_ref);
         self.next_ref += 1;
+        encoded.write(&mut self.pdf, image_id, &mut self.next_ref);
+        
+        // Geometry keeps using the original size, so resampling never moves an image
+        let image = EmbeddedImage {
+            id: image_id,
+            width: original_width as f32,
+            height: original_height as f32,
+            orientation,
+        };
+        self.image_refs.insert(field.field_id.clone(), image);
+        Ok(image)
+    }
+
+    /// Embed an SVG as vector paths in a form XObject that, like an image,
+    /// fills the unit square; fit modes treat its viewport as the pixel size.
+    fn embed_svg(&mut self, svg_data: &[u8], field: &FieldData) -> anyhow::Result<EmbeddedImage> {
+        let svg = SvgImage::parse(svg_data)?;
+        for feature in &svg.unsupported {
+            self.metadata.warnings.push(format!("Field {}: SVG {} not supported", field.field_id, feature));
+        }
+        
+        let (width, height) = (svg.width.round() as u32, svg.height.round() as u32);
+        self.metadata.images.push(ImageMetadata {
+            field_id: field.field_id.clone(),
+            original_width: width,
+            original_height: height,
+            original_bytes: svg_data.len(),
+            width,
+            height,
+            bytes: svg.data.len(),
+            resampled: false,
+        });
+        
+        let form_id = Ref::new(self.next_ref);
+        self.next_ref += 1;
+        svg.write(&mut self.pdf, form_id, &mut self.next_ref);
         
-        let mut image = self.pdf.image_xobject(image_id, rgb_img.as_raw());
-        image.width(width as i32);
-        image.height(height as i32);
-        image.color_space().device_rgb();
-        image.bits_per_component(8);
-        image.filter(Filter::DctDecode);
-        image.finish();
+        let image = EmbeddedImage {
+            id: form_id,
+            width: svg.width,
+            height: svg.height,
+            orientation: Orientation::NoTransforms,
+        };
+        self.image_refs.insert(field.field_id.clone(), image);
+        Ok(image)
+    }
+
+    fn render_embedded_image(&self, field: &FieldData, page_info: &crate::types::PdfPageInfo, image: EmbeddedImage, content: &mut Content) {
+        let (pdf_x, pdf_y, box_width, box_height) = self.convert_coordinates(field, page_info);
+        let (matrix, _) = self.image_matrix(field, page_info, image.width, image.height, image.orientation);
         
-        self.image_refs.insert(field_id.to_string(), (image_id, width, height));
-        Ok((image_id, width, height))
+        content.save_state();
+        // Cover-fit and rotated images must not spill over neighbouring content
+        content.rect(pdf_x, pdf_y, box_width, box_height);
+        content.clip_nonzero();
+        content.end_path();
+        content.transform(matrix);
+        content.x_object(Name(format!("Im{}", image.id.get()).as_bytes()));
+        content.restore_state();
     }
 
-    fn render_embedded_image(&self, field: &FieldData, page_info: &crate::types::PdfPageInfo, img_ref: Ref, content: &mut Content) {
+    /// Draw signature pad strokes straight into the page as vector paths,
+    /// placed like an image whose pixel size is the pad area.
+    fn render_strokes(&self, field: &FieldData, page_info: &crate::types::PdfPageInfo, signature: &StrokeSignature, content: &mut Content) -> anyhow::Result<()> {
+        let (x, y, width, height) = signature::bounds(signature)
+            .ok_or_else(|| anyhow::anyhow!("no stroke points or empty canvas"))?;
+        let color = signature::pen_color(signature)?;
         let (pdf_x, pdf_y, box_width, box_height) = self.convert_coordinates(field, page_info);
+        let (matrix, _) = self.image_matrix(field, page_info, width, height, Orientation::NoTransforms);
+        // Pad area onto the unit square, flipping the pad's downward y axis
+        let pad = [1.0 / width, 0.0, 0.0, -1.0 / height, -x / width, 1.0 + y / height];
         
-        // Get actual image dimensions
-        let (img_width, img_height) = self.image_refs.get(&field.field_id)
-            .map(|(_, w, h)| (*w as f32, *h as f32))
-            .unwrap_or((box_width, box_height));
+        content.save_state();
+        content.rect(pdf_x, pdf_y, box_width, box_height);
+        content.clip_nonzero();
+        content.end_path();
+        content.transform(concat(pad, matrix));
+        signature::draw(signature, color, content);
+        content.restore_state();
+        Ok(())
+    }
+
+    /// Matrix mapping the image XObject's unit square into the field box:
+    /// EXIF orientation, then `rotation` (clockwise), then the fit mode applied
+    /// to the rotated image's bounding box, shifted toward `focal_point`. Also
+    /// returns the points-per-pixel scale the image is drawn at.
+    fn image_matrix(&self, field: &FieldData, page_info: &crate::types::PdfPageInfo, width: f32, height: f32, orientation: Orientation) -> ([f32; 6], f32) {
+        let (pdf_x, pdf_y, box_width, box_height) = self.convert_coordinates(field, page_info);
+        let (w, h) = (width, height);
         
+        // Upright size, then the bounding box once rotated
+        let (upright_width, upright_height) = if images::swaps_axes(orientation) { (h, w) } else { (w, h) };
+        let (sin, cos) = field.rotation.unwrap_or(0.0).to_radians().sin_cos();
+        // Exact zeros at right angles instead of float noise in the matrix
+        let (sin, cos) = (snap_zero(sin), snap_zero(cos));
+        let bound_width = (upright_width * cos).abs() + (upright_height * sin).abs();
+        let bound_height = (upright_width * sin).abs() + (upright_height * cos).abs();
+        
+        let (render_width, render_height, offset_x, offset_y) =
+            Self::image_placement(field, box_width, box_height, bound_width, bound_height);
+        let scale_x = render_width / bound_width;
+        let scale_y = render_height / bound_height;
+        let mut center_x = offset_x + render_width / 2.0;
+        let mut center_y = offset_y + render_height / 2.0;
+        
+        // Move the focal point toward the box center, as far as the image still covers the box
+        if let Some(focal) = field.focal_point {
+            let ux = (focal.x.clamp(0.0, 1.0) - 0.5) * upright_width;
+            let uy = (0.5 - focal.y.clamp(0.0, 1.0)) * upright_height;
+            let focal_x = (ux * cos + uy * sin) * scale_x;
+            let focal_y = (uy * cos - ux * sin) * scale_y;
+            if render_width > box_width {
+                center_x = (box_width / 2.0 - focal_x).clamp(box_width - render_width / 2.0, render_width / 2.0);
+            }
+            if render_height > box_height {
+                center_y = (box_height / 2.0 - focal_y).clamp(box_height - render_height / 2.0, render_height / 2.0);
+            }
+        }
+        
+        // Pixels centered on the origin, turned upright, rotated, then scaled into place
+        let [a, b, c, d] = images::orientation_matrix(orientation);
+        let matrix = concat([w, 0.0, 0.0, h, -w / 2.0, -h / 2.0], [a, b, c, d, 0.0, 0.0]);
+        let matrix = concat(matrix, [cos, -sin, sin, cos, 0.0, 0.0]);
+        let matrix = concat(matrix, [
+            scale_x,
+            0.0,
+            0.0,
+            scale_y,
+            pdf_x + center_x,
+            pdf_y + center_y,
+        ]);
+        
+        (matrix, scale_x.max(scale_y))
+    }
+
+    /// Size and offset within the box of an image drawn with the field's fit mode.
+    fn image_placement(field: &FieldData, box_width: f32, box_height: f32, img_width: f32, img_height: f32) -> (f32, f32, f32, f32) {
         let fit_mode = field.fit_mode.as_ref().unwrap_or(&ImageFitMode::Contain);
         
-        let (render_width, render_height, offset_x, offset_y) = match fit_mode {
+        match fit_mode {
             ImageFitMode::Fill => {
                 (box_width, box_height, 0.0, 0.0)
             }
@@ -514,12 +1507,7 @@ impl PdfFieldRenderer {
                     (w, h, (box_width - w) / 2.0, (box_height - h) / 2.0)
                 }
             }
-        };
-        
-        content.save_state();
-        content.transform([render_width, 0.0, 0.0, render_height, pdf_x + offset_x, pdf_y + offset_y]);
-        content.x_object(Name(format!("Im{}", img_ref.get()).as_bytes()));
-        content.restore_state();
+        }
     }
 
     fn convert_coordinates(&self, field: &FieldData, page_info: &crate::types::PdfPageInfo) -> (f32, f32, f32, f32) {
@@ -533,3 +1521,19 @@ impl PdfFieldRenderer {
         (x, pdf_y, width, height)
     }
 }
+
+/// `m` followed by `n`, as PDF matrices (row vectors: `p' = p × m × n`).
+fn concat(m: [f32; 6], n: [f32; 6]) -> [f32; 6] {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+fn snap_zero(value: f32) -> f32 {
+    if value.abs() < 1e-6 { 0.0 } else { value }
+}
@@ -0,0 +1,93 @@
+use crate::inspect::{FormFieldInfo, PageInfo, TemplateInfo};
+use crate::types::{FieldData, FieldValue, ImageSource};
+
+/// 1x1 transparent PNG so scaffolded signature fields fill without errors.
+const PLACEHOLDER_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAAC0lEQVR4nGNgAAIAAAUAAXpeqz8AAAAASUVORK5CYII=";
+
+/// Starter field data for every fillable widget in the template, with boxes
+/// converted to the top-left coordinate system `fill` expects.
+///
+/// Radio groups get one entry, boxed on their first button, with every
+/// button's state in `options` and the selected or first one as the value.
+pub fn scaffold_fields(info: &TemplateInfo) -> Vec<FieldData> {
+    let mut fields: Vec<FieldData> = Vec::new();
+
+    for page in &info.pages {
+        for widget in &page.fields {
+            if widget.field_type == "radio" {
+                let group = fields.iter_mut()
+                    .find(|f| f.field_id == widget.name && matches!(f.value, FieldValue::Radio(_)));
+                if let Some(group) = group {
+                    add_radio_option(group, widget);
+                    continue;
+                }
+            }
+            let Some(value) = placeholder_value(widget) else {
+                continue;
+            };
+
+            let (x, y, width, height) = top_left_box(page, widget.rect);
+            let mut field = FieldData {
+                field_id: widget.name.clone(),
+                page: page.page_number,
+                x,
+                y,
+                width,
+                height,
+                value,
+                options: (!widget.options.is_empty()).then(|| widget.options.clone()),
+                ..Default::default()
+            };
+            if widget.field_type == "radio" {
+                add_radio_option(&mut field, widget);
+            }
+            fields.push(field);
+        }
+    }
+
+    fields
+}
+
+/// List a radio button's state in its group's `options`, unless the group
+/// has `/Opt` export values, which are listed already.
+fn add_radio_option(group: &mut FieldData, widget: &FormFieldInfo) {
+    let Some(state) = widget.on_state.clone().filter(|_| widget.options.is_empty()) else {
+        return;
+    };
+    let options = group.options.get_or_insert_with(Vec::new);
+    if !options.contains(&state) {
+        options.push(state);
+    }
+}
+
+/// Current value where there is one, otherwise a placeholder of the right type.
+fn placeholder_value(widget: &FormFieldInfo) -> Option<FieldValue> {
+    let current = widget.value.clone().filter(|v| !v.is_empty() && v != "Off");
+
+    let value = match widget.field_type.as_str() {
+        "text" => FieldValue::Text(current.unwrap_or_else(|| widget.name.clone())),
+        "checkbox" => FieldValue::Checkbox(current.is_some()),
+        "radio" => FieldValue::Radio(current.or_else(|| widget.on_state.clone()).unwrap_or_default()),
+        "dropdown" => FieldValue::Dropdown(
+            current.or_else(|| widget.options.first().cloned()).unwrap_or_default(),
+        ),
+        "signature" => FieldValue::Signature(ImageSource::Base64(PLACEHOLDER_PNG.to_string())),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Convert a PDF rect (bottom-left origin) to `(x, y, width, height)` measured
+/// from the page's top-left corner.
+fn top_left_box(page: &PageInfo, rect: [f32; 4]) -> (f32, f32, f32, f32) {
+    let [origin_x, origin_y, _, _] = page.media_box;
+    let width = rect[2] - rect[0];
+    let height = rect[3] - rect[1];
+    let x = rect[0] - origin_x;
+    let y = page.height - (rect[3] - origin_y);
+    (round(x), round(y), round(width), round(height))
+}
+
+fn round(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
@@ -0,0 +1,216 @@
+use crate::cache::TemplateCache;
+use crate::types::{FieldData, TemplateSource, TextOverflow};
+use crate::filler::{fetch_fields, fill_fetched};
+use crate::{inspect, load_template, parse_template_source, FillOptions, PreparedTemplate};
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Response header carrying the fill's `ProcessingMetadata` as JSON.
+pub const METADATA_HEADER: &str = "x-fill-metadata";
+
+/// Longest `x-fill-metadata` value sent; proxies commonly refuse responses
+/// with more than 8-16 KB of headers.
+const MAX_METADATA_HEADER_BYTES: usize = 8 * 1024;
+
+/// Requests carry base64 images and signatures, so allow well beyond axum's 2 MB default.
+const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Shared by every request: one HTTP client (connection pool) and one template cache.
+pub struct ServerState {
+    pub client: reqwest::Client,
+    pub cache: Option<TemplateCache>,
+    pub options: FillOptions,
+    /// Accept local template paths; off by default, as they let any client
+    /// read PDFs the server can access
+    pub allow_paths: bool,
+}
+
+#[derive(Deserialize)]
+struct FillRequest {
+    /// Path, URL or `TemplateSource` object, as accepted by `fill --template`
+    template: serde_json::Value,
+    fields: Vec<FieldData>,
+    #[serde(default)]
+    keep_fields: Option<bool>,
+    #[serde(default)]
+    text_overflow: Option<TextOverflow>,
+    #[serde(default)]
+    max_dpi: Option<f32>,
+    #[serde(default)]
+    jpeg_quality: Option<u8>,
+    #[serde(default)]
+    cache_refresh: bool,
+    /// Answer with `{"pdf": <base64>, "metadata": ...}` instead of PDF bytes
+    /// and the metadata header
+    #[serde(default)]
+    metadata_in_body: bool,
+}
+
+#[derive(Deserialize)]
+struct InspectRequest {
+    template: serde_json::Value,
+    #[serde(default)]
+    cache_refresh: bool,
+}
+
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// Malformed JSON and field data the types don't accept are the client's fault.
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, rejection.body_text())
+    }
+}
+
+fn internal_error(e: impl std::fmt::Display) -> ApiError {
+    ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+pub async fn serve(host: &str, port: u16, state: ServerState) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/fill", post(fill))
+        .route("/inspect", post(inspect))
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .with_state(Arc::new(state));
+
+    let listener = tokio::net::TcpListener::bind((host, port)).await
+        .map_err(|e| anyhow::anyhow!("Failed to bind {}:{}: {}", host, port, e))?;
+    log::info!("Listening on http://{}", listener.local_addr()?);
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+            log::info!("Shutting down");
+        })
+        .await?;
+    Ok(())
+}
+
+async fn healthz() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+async fn fill(
+    State(state): State<Arc<ServerState>>,
+    request: Result<Json<FillRequest>, JsonRejection>,
+) -> Result<Response, ApiError> {
+    let start = std::time::Instant::now();
+    let Json(request) = request?;
+    let field_count = request.fields.len();
+    if request.jpeg_quality.is_some_and(|quality| !(1..=100).contains(&quality)) {
+        return Err(ApiError(StatusCode::BAD_REQUEST, "jpeg_quality must be between 1 and 100".to_string()));
+    }
+    if request.max_dpi.is_some_and(|dpi| dpi.is_nan() || dpi <= 0.0) {
+        return Err(ApiError(StatusCode::BAD_REQUEST, "max_dpi must be positive".to_string()));
+    }
+
+    let template = load(&state, &request.template, request.cache_refresh).await?;
+
+    let mut options = state.options.clone();
+    if let Some(keep_fields) = request.keep_fields {
+        options.flatten = !keep_fields;
+    }
+    if let Some(text_overflow) = request.text_overflow {
+        options.text_overflow = text_overflow;
+    }
+    options.max_dpi = request.max_dpi.or(options.max_dpi);
+    options.jpeg_quality = request.jpeg_quality.or(options.jpeg_quality);
+
+    let fields = fetch_fields(&state.client, request.fields, &options).await
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+    // Rendering and merging are CPU-bound, and the python and bun backends
+    // wait on a subprocess
+    let (pdf, metadata) = tokio::task::spawn_blocking(move || fill_fetched(&template, fields, &options)).await
+        .map_err(internal_error)?
+        .map_err(internal_error)?;
+
+    log::info!("POST /fill: {} field(s) in {:.2}ms", field_count, start.elapsed().as_secs_f64() * 1000.0);
+
+    if request.metadata_in_body {
+        let pdf = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &pdf);
+        return Ok(Json(serde_json::json!({ "pdf": pdf, "metadata": metadata })).into_response());
+    }
+
+    let mut metadata_json = ascii_json(&serde_json::to_string(&metadata).map_err(internal_error)?);
+    if metadata_json.len() > MAX_METADATA_HEADER_BYTES {
+        // Just the counts; the full metadata needs `metadata_in_body`
+        metadata_json = serde_json::json!({
+            "fieldsProcessed": metadata.fields_processed,
+            "fieldsSkipped": metadata.fields_skipped,
+            "warningCount": metadata.warnings.len(),
+            "errorCount": metadata.errors.len(),
+            "truncated": true,
+        }).to_string();
+    }
+    let metadata_header = HeaderValue::from_str(&metadata_json).map_err(internal_error)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/pdf")),
+            (header::HeaderName::from_static(METADATA_HEADER), metadata_header),
+        ],
+        pdf,
+    ).into_response())
+}
+
+async fn inspect(
+    State(state): State<Arc<ServerState>>,
+    request: Result<Json<InspectRequest>, JsonRejection>,
+) -> Result<Json<inspect::TemplateInfo>, ApiError> {
+    let Json(request) = request?;
+    let template = load(&state, &request.template, request.cache_refresh).await?;
+    let info = inspect::inspect_template(&template.document).map_err(internal_error)?;
+    log::info!("POST /inspect: {} page(s)", info.page_count);
+    Ok(Json(info))
+}
+
+async fn load(state: &ServerState, template: &serde_json::Value, cache_refresh: bool) -> Result<PreparedTemplate, ApiError> {
+    // Strings are paths or URLs; objects are a TemplateSource config
+    let source = match template {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let parsed = parse_template_source(&source)
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, format!("Invalid template source: {}", e)))?;
+    if matches!(parsed, TemplateSource::Path(_)) && !state.allow_paths {
+        return Err(ApiError(
+            StatusCode::FORBIDDEN,
+            "Template paths are disabled; pass a URL or start the server with --allow-paths".to_string(),
+        ));
+    }
+
+    let bytes = load_template(&state.client, &source, state.cache.as_ref(), cache_refresh).await
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, format!("Failed to load template: {}", e)))?;
+    tokio::task::spawn_blocking(move || PreparedTemplate::new(bytes)).await
+        .map_err(internal_error)?
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Header values must be ASCII, so escape everything else as `\uXXXX`.
+fn ascii_json(json: &str) -> String {
+    let mut escaped = String::with_capacity(json.len());
+    for c in json.chars() {
+        if c.is_ascii() && !c.is_ascii_control() {
+            escaped.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                escaped.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    escaped
+}
@@ -0,0 +1,121 @@
+use crate::color::Color;
+use crate::types::{EllipseShape, LineDirection, LineShape, RectShape};
+use pdf_writer::Content;
+
+/// Control point distance for a quarter circle of radius 1 drawn as a cubic.
+const KAPPA: f32 = 0.552_284_8;
+
+/// Stroke a line across the box; horizontal lines run through its middle.
+pub fn line(content: &mut Content, shape: &LineShape, x: f32, y: f32, width: f32, height: f32) -> anyhow::Result<()> {
+    let color = optional_color(shape.color.as_deref())?.unwrap_or(Color::BLACK);
+    let ((x1, y1), (x2, y2)) = match shape.direction {
+        LineDirection::Horizontal => ((x, y + height / 2.0), (x + width, y + height / 2.0)),
+        LineDirection::Vertical => ((x + width / 2.0, y), (x + width / 2.0, y + height)),
+        LineDirection::DiagonalDown => ((x, y + height), (x + width, y)),
+        LineDirection::DiagonalUp => ((x, y), (x + width, y + height)),
+    };
+
+    content.save_state();
+    color.set_stroke(content);
+    content.set_line_width(shape.line_width.unwrap_or(1.0));
+    content.move_to(x1, y1);
+    content.line_to(x2, y2);
+    content.stroke();
+    content.restore_state();
+    Ok(())
+}
+
+/// Fill and/or stroke a (rounded) rectangle. The border is drawn inside the
+/// box, so it never spills over neighbouring content.
+pub fn rect(content: &mut Content, shape: &RectShape, x: f32, y: f32, width: f32, height: f32) -> anyhow::Result<()> {
+    let paint = Paint::new(shape.stroke_color.as_deref(), shape.fill_color.as_deref(), shape.border_width)?;
+    let inset = paint.inset();
+    let (x, y, width, height) = (x + inset, y + inset, (width - 2.0 * inset).max(0.0), (height - 2.0 * inset).max(0.0));
+    let radius = (shape.corner_radius.unwrap_or(0.0) - inset).clamp(0.0, width.min(height) / 2.0);
+
+    paint.begin(content);
+    if radius > 0.0 {
+        let k = radius * (1.0 - KAPPA);
+        content.move_to(x + radius, y);
+        content.line_to(x + width - radius, y);
+        content.cubic_to(x + width - k, y, x + width, y + k, x + width, y + radius);
+        content.line_to(x + width, y + height - radius);
+        content.cubic_to(x + width, y + height - k, x + width - k, y + height, x + width - radius, y + height);
+        content.line_to(x + radius, y + height);
+        content.cubic_to(x + k, y + height, x, y + height - k, x, y + height - radius);
+        content.line_to(x, y + radius);
+        content.cubic_to(x, y + k, x + k, y, x + radius, y);
+        content.close_path();
+    } else {
+        content.rect(x, y, width, height);
+    }
+    paint.end(content);
+    Ok(())
+}
+
+/// Fill and/or stroke the ellipse inscribed in the box, border inside it.
+pub fn ellipse(content: &mut Content, shape: &EllipseShape, x: f32, y: f32, width: f32, height: f32) -> anyhow::Result<()> {
+    let paint = Paint::new(shape.stroke_color.as_deref(), shape.fill_color.as_deref(), shape.border_width)?;
+    let inset = paint.inset();
+    let (rx, ry) = ((width / 2.0 - inset).max(0.0), (height / 2.0 - inset).max(0.0));
+    let (cx, cy) = (x + width / 2.0, y + height / 2.0);
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+
+    paint.begin(content);
+    content.move_to(cx + rx, cy);
+    content.cubic_to(cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry);
+    content.cubic_to(cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy);
+    content.cubic_to(cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry);
+    content.cubic_to(cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy);
+    content.close_path();
+    paint.end(content);
+    Ok(())
+}
+
+/// Stroke and fill of a closed shape. Without either color the outline is
+/// stroked in black.
+struct Paint {
+    stroke: Option<Color>,
+    fill: Option<Color>,
+    border_width: f32,
+}
+
+impl Paint {
+    fn new(stroke_color: Option<&str>, fill_color: Option<&str>, border_width: Option<f32>) -> anyhow::Result<Self> {
+        let fill = optional_color(fill_color)?;
+        let stroke = match optional_color(stroke_color)? {
+            None if fill.is_none() => Some(Color::BLACK),
+            stroke => stroke,
+        };
+        Ok(Self { stroke, fill, border_width: border_width.unwrap_or(1.0).max(0.0) })
+    }
+
+    fn inset(&self) -> f32 {
+        if self.stroke.is_some() { self.border_width / 2.0 } else { 0.0 }
+    }
+
+    fn begin(&self, content: &mut Content) {
+        content.save_state();
+        if let Some(stroke) = self.stroke {
+            stroke.set_stroke(content);
+            content.set_line_width(self.border_width);
+        }
+        if let Some(fill) = self.fill {
+            fill.set_fill(content);
+        }
+    }
+
+    fn end(&self, content: &mut Content) {
+        match (self.fill.is_some(), self.stroke.is_some() && self.border_width > 0.0) {
+            (true, true) => content.fill_nonzero_and_stroke(),
+            (true, false) => content.fill_nonzero(),
+            (false, true) => content.stroke(),
+            (false, false) => content.end_path(),
+        };
+        content.restore_state();
+    }
+}
+
+fn optional_color(color: Option<&str>) -> anyhow::Result<Option<Color>> {
+    color.map(Color::parse).transpose()
+}
@@ -0,0 +1,163 @@
+use crate::color::Color;
+use crate::types::{StrokePoint, StrokeSignature};
+use pdf_writer::types::{LineCapStyle, LineJoinStyle};
+use pdf_writer::Content;
+
+const DEFAULT_PEN_WIDTH: f32 = 2.0;
+
+/// Area of the pad the signature is placed from, as `(x, y, width, height)`:
+/// the canvas if given, otherwise the strokes' bounding box grown by half the
+/// pen width so line ends aren't cut off. `None` when there is nothing to draw.
+pub fn bounds(signature: &StrokeSignature) -> Option<(f32, f32, f32, f32)> {
+    let (x, y, width, height) = match signature.canvas {
+        Some(canvas) => (0.0, 0.0, canvas.width, canvas.height),
+        None => {
+            let strokes = finite_strokes(signature);
+            let mut points = strokes.iter().flatten();
+            let first = points.next()?;
+            let (mut min_x, mut min_y, mut max_x, mut max_y) = (first.x, first.y, first.x, first.y);
+            for point in points {
+                min_x = min_x.min(point.x);
+                min_y = min_y.min(point.y);
+                max_x = max_x.max(point.x);
+                max_y = max_y.max(point.y);
+            }
+            let margin = pen_width(signature) / 2.0;
+            (min_x - margin, min_y - margin, max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin)
+        }
+    };
+    (width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite()).then_some((x, y, width, height))
+}
+
+/// Pen color, black by default.
+pub fn pen_color(signature: &StrokeSignature) -> anyhow::Result<Color> {
+    match signature.pen_color.as_deref() {
+        Some(color) => Color::parse(color),
+        None => Ok(Color::BLACK),
+    }
+}
+
+/// Stroke every pen stroke in pad coordinates, smoothed into cubic Béziers
+/// through its points. With `min_pen_width` each segment is stroked on its
+/// own at the width of its average pressure; round caps hide the seams.
+pub fn draw(signature: &StrokeSignature, color: Color, content: &mut Content) {
+    let varies = signature.min_pen_width.is_some();
+    color.set_stroke(content);
+    content.set_line_cap(LineCapStyle::RoundCap);
+    content.set_line_join(LineJoinStyle::RoundJoin);
+    if !varies {
+        content.set_line_width(pen_width(signature));
+    }
+
+    for stroke in finite_strokes(signature) {
+        match stroke.as_slice() {
+            [] => {}
+            // A tap: a zero-length line, which round caps draw as a dot
+            [point] => {
+                if varies {
+                    content.set_line_width(point_width(signature, point));
+                }
+                content.move_to(point.x, point.y);
+                content.line_to(point.x, point.y);
+                content.stroke();
+            }
+            points if varies => {
+                for i in 0..points.len() - 1 {
+                    let width = (point_width(signature, &points[i]) + point_width(signature, &points[i + 1])) / 2.0;
+                    content.set_line_width(width);
+                    content.move_to(points[i].x, points[i].y);
+                    segment(points, i, content);
+                    content.stroke();
+                }
+            }
+            points => {
+                content.move_to(points[0].x, points[0].y);
+                for i in 0..points.len() - 1 {
+                    segment(points, i, content);
+                }
+                content.stroke();
+            }
+        }
+    }
+}
+
+/// The strokes without points whose coordinates aren't finite numbers (JSON
+/// numbers past the `f32` range read as infinity), which would put NaN or
+/// infinite operands into the path.
+fn finite_strokes(signature: &StrokeSignature) -> Vec<Vec<StrokePoint>> {
+    signature.strokes.iter()
+        .map(|stroke| stroke.iter().filter(|p| p.x.is_finite() && p.y.is_finite()).copied().collect())
+        .collect()
+}
+
+fn pen_width(signature: &StrokeSignature) -> f32 {
+    signature.pen_width.filter(|w| w.is_finite() && *w >= 0.0).unwrap_or(DEFAULT_PEN_WIDTH)
+}
+
+fn point_width(signature: &StrokeSignature, point: &StrokePoint) -> f32 {
+    let max = pen_width(signature);
+    let min = signature.min_pen_width.filter(|w| w.is_finite() && *w >= 0.0).unwrap_or(max);
+    let pressure = point.pressure.filter(|p| p.is_finite()).unwrap_or(1.0);
+    min + (max - min) * pressure.clamp(0.0, 1.0)
+}
+
+/// Catmull-Rom curve from `points[i]` to `points[i + 1]` as a cubic Bézier,
+/// with the end points repeated as their own neighbours.
+fn segment(points: &[StrokePoint], i: usize, content: &mut Content) {
+    let p0 = &points[i.saturating_sub(1)];
+    let p1 = &points[i];
+    let p2 = &points[i + 1];
+    let p3 = &points[(i + 2).min(points.len() - 1)];
+    content.cubic_to(
+        p1.x + (p2.x - p0.x) / 6.0,
+        p1.y + (p2.y - p0.y) / 6.0,
+        p2.x - (p3.x - p1.x) / 6.0,
+        p2.y - (p3.y - p1.y) / 6.0,
+        p2.x,
+        p2.y,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32, pressure: Option<f32>) -> StrokePoint {
+        StrokePoint { x, y, pressure }
+    }
+
+    fn signature(strokes: Vec<Vec<StrokePoint>>) -> StrokeSignature {
+        StrokeSignature {
+            strokes,
+            canvas: None,
+            pen_color: None,
+            pen_width: Some(f32::INFINITY),
+            min_pen_width: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn bounds_skip_non_finite_points() {
+        let signature = signature(vec![vec![
+            point(10.0, 20.0, None),
+            point(f32::NAN, 5.0, None),
+            point(30.0, f32::INFINITY, None),
+            point(50.0, 40.0, None),
+        ]]);
+        // Default 2-unit pen, so a 1-unit margin
+        assert_eq!(bounds(&signature), Some((9.0, 19.0, 42.0, 22.0)));
+    }
+
+    #[test]
+    fn draw_writes_only_finite_operands() {
+        let signature = signature(vec![
+            vec![point(0.0, 0.0, Some(f32::NAN)), point(f32::NEG_INFINITY, 1.0, Some(0.5)), point(10.0, 10.0, Some(f32::INFINITY))],
+            vec![point(f32::NAN, f32::NAN, None)],
+        ]);
+        let mut content = Content::new();
+        draw(&signature, Color::BLACK, &mut content);
+        let ops = String::from_utf8(content.finish()).unwrap();
+        assert!(ops.contains(" c\n"), "{}", ops);
+        assert!(!ops.contains("NaN") && !ops.contains("inf"), "{}", ops);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::fonts::deflate;
+use pdf_writer::types::{LineCapStyle, LineJoinStyle};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref};
+use std::collections::BTreeSet;
+use std::io::Read;
+use usvg::tiny_skia_path::{self, PathSegment};
+use usvg::{Node, Paint, PaintOrder, Transform};
+
+/// Whether image bytes are an SVG document, plain or gzip-compressed (`.svgz`).
+pub fn is_svg(data: &[u8]) -> bool {
+    if data.starts_with(&[0x1F, 0x8B]) {
+        // Only the start of the stream is needed, which a truncated one still has
+        let mut head = Vec::new();
+        let _ = flate2::read::GzDecoder::new(data).take(4096).read_to_end(&mut head);
+        return is_svg_text(&head);
+    }
+    is_svg_text(&data[..data.len().min(4096)])
+}
+
+/// Whether the start of a document has an `<svg` root element, allowing for
+/// an XML declaration, doctype and comments before it.
+fn is_svg_text(head: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(head);
+    let head = head.trim_start_matches('\u{FEFF}').trim_start();
+    head.starts_with('<') && head.contains("<svg")
+}
+
+/// An SVG converted to PDF vector paths, ready to be written as a Form XObject.
+pub struct SvgImage {
+    /// Size of the SVG viewport, in user units
+    pub width: f32,
+    pub height: f32,
+    /// Flate-compressed content stream
+    pub data: Vec<u8>,
+    /// Constant alpha of each graphics state the content refers to as `Gs{index}`
+    alphas: Vec<f32>,
+    /// SVG features that were dropped, e.g. `"clip paths"`
+    pub unsupported: BTreeSet<&'static str>,
+}
+
+impl SvgImage {
+    /// Parse an SVG and convert its paths: solid fills and strokes with their
+    /// width, caps, joins, dashes and opacity. Gradients fall back to their
+    /// first stop color; text, embedded images, clip paths, masks, filters
+    /// and patterns are dropped and listed in `unsupported`.
+    pub fn parse(data: &[u8]) -> anyhow::Result<Self> {
+        let tree = usvg::Tree::from_data(data, &usvg::Options::default())?;
+        let size = tree.size();
+
+        let mut converter = Converter {
+            content: Content::new(),
+            alphas: Vec::new(),
+            unsupported: BTreeSet::new(),
+        };
+        // SVG's y axis points down
+        converter.content.transform([1.0, 0.0, 0.0, -1.0, 0.0, size.height()]);
+        converter.group(tree.root(), 1.0);
+
+        Ok(Self {
+            width: size.width(),
+            height: size.height(),
+            data: deflate(&converter.content.finish())?,
+            alphas: converter.alphas,
+            unsupported: converter.unsupported,
+        })
+    }
+
+    /// Write the form (and its graphics states) at `form_id`. Like an image
+    /// XObject, the form is drawn into the unit square.
+    pub fn write(&self, pdf: &mut Pdf, form_id: Ref, next_ref: &mut i32) {
+        let states: Vec<Ref> = self.alphas.iter().map(|&alpha| {
+            let state_id = Ref::new(*next_ref);
+            *next_ref += 1;
+            pdf.ext_graphics(state_id)
+                .non_stroking_alpha(alpha)
+                .stroking_alpha(alpha);
+            state_id
+        }).collect();
+
+        let mut form = pdf.form_xobject(form_id, &self.data);
+        form.filter(Filter::FlateDecode);
+        form.bbox(Rect::new(0.0, 0.0, self.width, self.height));
+        form.matrix([1.0 / self.width, 0.0, 0.0, 1.0 / self.height, 0.0, 0.0]);
+        if !states.is_empty() {
+            let mut resources = form.resources();
+            let mut ext_g_states = resources.ext_g_states();
+            for (index, state_id) in states.iter().enumerate() {
+                ext_g_states.pair(Name(format!("Gs{}", index).as_bytes()), *state_id);
+            }
+        }
+        form.finish();
+    }
+}
+
+struct Converter {
+    content: Content,
+    alphas: Vec<f32>,
+    unsupported: BTreeSet<&'static str>,
+}
+
+impl Converter {
+    /// Group opacity is folded into each path's own opacity, which matches
+    /// SVG wherever a group's children don't overlap.
+    fn group(&mut self, group: &usvg::Group, opacity: f32) {
+        if group.clip_path().is_some() {
+            self.unsupported.insert("clip paths");
+        }
+        if group.mask().is_some() {
+            self.unsupported.insert("masks");
+        }
+        if !group.filters().is_empty() {
+            self.unsupported.insert("filters");
+        }
+
+        let opacity = opacity * group.opacity().get();
+        for node in group.children() {
+            match node {
+                Node::Group(group) => self.group(group, opacity),
+                Node::Path(path) => self.path(path, opacity),
+                Node::Image(_) => {
+                    self.unsupported.insert("embedded images");
+                }
+                Node::Text(_) => {
+                    self.unsupported.insert("text");
+                }
+            }
+        }
+    }
+
+    fn path(&mut self, path: &usvg::Path, opacity: f32) {
+        if !path.is_visible() {
+            return;
+        }
+        match path.paint_order() {
+            PaintOrder::FillAndStroke => {
+                self.fill(path, opacity);
+                self.stroke(path, opacity);
+            }
+            PaintOrder::StrokeAndFill => {
+                self.stroke(path, opacity);
+                self.fill(path, opacity);
+            }
+        }
+    }
+
+    fn fill(&mut self, path: &usvg::Path, opacity: f32) {
+        let Some(fill) = path.fill() else { return };
+        let Some([r, g, b]) = self.color(fill.paint()) else { return };
+
+        self.content.save_state();
+        self.set_alpha(opacity * fill.opacity().get());
+        self.content.transform(matrix(path.abs_transform()));
+        self.content.set_fill_rgb(r, g, b);
+        self.path_data(path.data());
+        match fill.rule() {
+            usvg::FillRule::NonZero => self.content.fill_nonzero(),
+            usvg::FillRule::EvenOdd => self.content.fill_even_odd(),
+        };
+        self.content.restore_state();
+    }
+
+    fn stroke(&mut self, path: &usvg::Path, opacity: f32) {
+        let Some(stroke) = path.stroke() else { return };
+        let Some([r, g, b]) = self.color(stroke.paint()) else { return };
+
+        self.content.save_state();
+        self.set_alpha(opacity * stroke.opacity().get());
+        // Stroke width and dashes are in path units, so they scale with the transform
+        self.content.transform(matrix(path.abs_transform()));
+        self.content.set_stroke_rgb(r, g, b);
+        self.content.set_line_width(stroke.width().get());
+        self.content.set_line_cap(match stroke.linecap() {
+            usvg::LineCap::Butt => LineCapStyle::ButtCap,
+            usvg::LineCap::Round => LineCapStyle::RoundCap,
+            usvg::LineCap::Square => LineCapStyle::ProjectingSquareCap,
+        });
+        self.content.set_line_join(match stroke.linejoin() {
+            usvg::LineJoin::Miter | usvg::LineJoin::MiterClip => LineJoinStyle::MiterJoin,
+            usvg::LineJoin::Round => LineJoinStyle::RoundJoin,
+            usvg::LineJoin::Bevel => LineJoinStyle::BevelJoin,
+        });
+        self.content.set_miter_limit(stroke.miterlimit().get());
+        if let Some(dashes) = stroke.dasharray() {
+            self.content.set_dash_pattern(dashes.iter().copied(), stroke.dashoffset());
+        }
+        self.path_data(path.data());
+        self.content.stroke();
+        self.content.restore_state();
+    }
+
+    fn color(&mut self, paint: &Paint) -> Option<[f32; 3]> {
+        let color = match paint {
+            Paint::Color(color) => *color,
+            Paint::LinearGradient(gradient) => {
+                self.unsupported.insert("gradients");
+                gradient.stops().first()?.color()
+            }
+            Paint::RadialGradient(gradient) => {
+                self.unsupported.insert("gradients");
+                gradient.stops().first()?.color()
+            }
+            Paint::Pattern(_) => {
+                self.unsupported.insert("patterns");
+                return None;
+            }
+        };
+        Some([color.red, color.green, color.blue].map(|c| c as f32 / 255.0))
+    }
+
+    fn set_alpha(&mut self, alpha: f32) {
+        if alpha >= 1.0 {
+            return;
+        }
+        let index = match self.alphas.iter().position(|&a| a == alpha) {
+            Some(index) => index,
+            None => {
+                self.alphas.push(alpha);
+                self.alphas.len() - 1
+            }
+        };
+        self.content.set_parameters(Name(format!("Gs{}", index).as_bytes()));
+    }
+
+    fn path_data(&mut self, data: &tiny_skia_path::Path) {
+        let mut start = tiny_skia_path::Point::zero();
+        let mut last = start;
+        for segment in data.segments() {
+            match segment {
+                PathSegment::MoveTo(p) => {
+                    self.content.move_to(p.x, p.y);
+                    start = p;
+                    last = p;
+                }
+                PathSegment::LineTo(p) => {
+                    self.content.line_to(p.x, p.y);
+                    last = p;
+                }
+                // PDF has no quadratic curves; raise them to cubics
+                PathSegment::QuadTo(c, p) => {
+                    self.content.cubic_to(
+                        last.x + (c.x - last.x) * 2.0 / 3.0,
+                        last.y + (c.y - last.y) * 2.0 / 3.0,
+                        p.x + (c.x - p.x) * 2.0 / 3.0,
+                        p.y + (c.y - p.y) * 2.0 / 3.0,
+                        p.x,
+                        p.y,
+                    );
+                    last = p;
+                }
+                PathSegment::CubicTo(c1, c2, p) => {
+                    self.content.cubic_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y);
+                    last = p;
+                }
+                PathSegment::Close => {
+                    self.content.close_path();
+                    last = start;
+                }
+            }
+        }
+    }
+}
+
+fn matrix(transform: Transform) -> [f32; 6] {
+    [transform.sx, transform.ky, transform.kx, transform.sy, transform.tx, transform.ty]
+}
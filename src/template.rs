@@ -0,0 +1,146 @@
+use crate::cache::{CacheEntry, TemplateCache};
+use crate::fetcher;
+use crate::types::{self, TemplateSource, UrlConfig};
+use lopdf::Document;
+use reqwest::Client;
+
+/// A template parsed once and reused for every fill.
+pub struct PreparedTemplate {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) document: Document,
+    pub(crate) pdf_info: types::PdfDocument,
+}
+
+impl PreparedTemplate {
+    pub fn new(bytes: Vec<u8>) -> anyhow::Result<Self> {
+        // Load PDF document using Cursor (same as srv-ocr)
+        let document = Document::load_from(std::io::Cursor::new(&bytes))
+            .map_err(|e| anyhow::anyhow!("Failed to load PDF document: {}", e))?;
+        let pdf_info = types::extract_pdf_info(&document)?;
+        Ok(Self { bytes, document, pdf_info })
+    }
+
+    /// Load and parse a template; see [`load_template`].
+    pub async fn load(
+        client: &Client,
+        template: &str,
+        cache: Option<&TemplateCache>,
+        cache_refresh: bool,
+    ) -> anyhow::Result<Self> {
+        Self::new(load_template(client, template, cache, cache_refresh).await?)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+}
+
+/// Load template bytes from a path, URL or JSON `TemplateSource`, using the
+/// template cache for remote sources when given. Progress goes to stderr so
+/// commands that print JSON keep stdout clean.
+pub async fn load_template(
+    client: &Client,
+    template: &str,
+    cache: Option<&TemplateCache>,
+    cache_refresh: bool,
+) -> anyhow::Result<Vec<u8>> {
+    let template_source = parse_template_source(template)?;
+
+    // Load template bytes (with caching if enabled)
+    let template_bytes = if let Some(cache) = cache.filter(|_| !matches!(template_source, TemplateSource::Path(_))) {
+        let cache_key = TemplateCache::generate_key(template);
+
+        if cache_refresh {
+            log::info!("Forcing cache refresh...");
+            fetch_and_cache_template(client, &template_source, cache, &cache_key).await?
+        } else if let Some(entry) = cache.get(&cache_key) {
+            log::info!("Using cached template");
+
+            // Validate with server if we have ETag/Last-Modified
+            if entry.etag.is_some() || entry.last_modified.is_some() {
+                match validate_cache(client, &template_source, &entry).await {
+                    Ok(true) => entry.template_bytes,
+                    Ok(false) => {
+                        log::info!("Template updated, refreshing cache...");
+                        fetch_and_cache_template(client, &template_source, cache, &cache_key).await?
+                    }
+                    Err(_) => {
+                        log::warn!("Cache validation failed, using cached version");
+                        entry.template_bytes
+                    }
+                }
+            } else {
+                entry.template_bytes
+            }
+        } else {
+            log::info!("Fetching and caching template...");
+            fetch_and_cache_template(client, &template_source, cache, &cache_key).await?
+        }
+    } else {
+        match template_source {
+            TemplateSource::Path(path) => std::fs::read(&path)?,
+            TemplateSource::Url(url_config) => {
+                log::info!("Fetching template from URL...");
+                fetcher::fetch_url_with_config(client, &url_config).await?
+            }
+        }
+    };
+
+    Ok(template_bytes)
+}
+
+/// A `--template` value as a source: a JSON `TemplateSource` object, an
+/// http(s) URL, or else a local path.
+pub fn parse_template_source(template: &str) -> anyhow::Result<TemplateSource> {
+    Ok(if template.starts_with('{') {
+        serde_json::from_str(template)?
+    } else if template.starts_with("http://") || template.starts_with("https://") {
+        TemplateSource::Url(UrlConfig {
+            url: template.to_string(),
+            method: None,
+            headers: None,
+            body: None,
+        })
+    } else {
+        TemplateSource::Path(template.to_string())
+    })
+}
+
+async fn fetch_and_cache_template(
+    client: &Client,
+    source: &TemplateSource,
+    cache: &TemplateCache,
+    cache_key: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let (bytes, etag, last_modified) = match source {
+        TemplateSource::Path(_) => unreachable!(),
+        TemplateSource::Url(config) => fetcher::fetch_with_headers(client, config).await?,
+    };
+
+    let entry = CacheEntry {
+        template_bytes: bytes.clone(),
+        cached_at: chrono::Utc::now(),
+        etag,
+        last_modified,
+    };
+
+    cache.set(cache_key, entry)?;
+    Ok(bytes)
+}
+
+async fn validate_cache(
+    client: &Client,
+    source: &TemplateSource,
+    entry: &CacheEntry,
+) -> anyhow::Result<bool> {
+    match source {
+        TemplateSource::Path(_) => Ok(true),
+        TemplateSource::Url(config) => {
+            fetcher::validate_cache(client, config, entry.etag.as_deref(), entry.last_modified.as_deref()).await
+        }
+    }
+}
@@ -20,10 +20,30 @@ pub struct ProcessingMetadata {
     pub pages: Vec<PageMetadata>,
     pub fields_processed: usize,
     pub fields_skipped: usize,
+    /// Size of every embedded image before and after resampling
+    #[serde(default)]
+    pub images: Vec<ImageMetadata>,
+    /// Font size chosen for each `auto_fit` or `shrink` text field
+    #[serde(default)]
+    pub text_fits: Vec<TextFitMetadata>,
+    /// Continuation sheets appended for `continue` text fields
+    #[serde(default)]
+    pub continuations: Vec<ContinuationMetadata>,
     pub warnings: Vec<String>,
     pub errors: Vec<String>,
 }
 
+impl ProcessingMetadata {
+    /// Template page index for each overlay page, in overlay order.
+    ///
+    /// The renderer only emits overlay pages for template pages that have
+    /// fields, so overlay page `i` belongs on template page `page_map()[i]`.
+    /// Continuation sheets are the overlay pages after these.
+    pub fn page_map(&self) -> Vec<u32> {
+        self.pages.iter().map(|p| p.page_number).collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PageMetadata {
@@ -34,50 +54,176 @@ pub struct PageMetadata {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMetadata {
+    pub field_id: String,
+    pub original_width: u32,
+    pub original_height: u32,
+    pub original_bytes: usize,
+    pub width: u32,
+    pub height: u32,
+    /// Image stream plus soft mask, as embedded
+    pub bytes: usize,
+    pub resampled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFitMetadata {
+    pub field_id: String,
+    pub font_size: f32,
+    pub lines: usize,
+    /// False when the text overflows even at `min_font_size`
+    pub fits: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuationMetadata {
+    pub field_id: String,
+    /// Index of the sheet's first page in the filled PDF
+    pub first_page: u32,
+    pub pages: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FieldData {
+    /// Overlay field name, or the fully qualified name of a template form
+    /// field when no coordinates are given
     pub field_id: String,
+    #[serde(default)]
     pub page: u32,
+    #[serde(default)]
     pub x: f32,
+    #[serde(default)]
     pub y: f32,
+    #[serde(default)]
     pub width: f32,
+    #[serde(default)]
     pub height: f32,
     #[serde(flatten)]
     pub value: FieldValue,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub font_size: Option<f32>,
-    #[serde(default)]
+    /// Smallest size `auto_fit` and `shrink` text and typed signatures may shrink to (default 6)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_font_size: Option<f32>,
+    /// Distance between baselines of wrapped text, in multiples of the font
+    /// size (default 1.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_height: Option<f32>,
+    /// Extra space in points after each `\n`-separated paragraph
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paragraph_spacing: Option<f32>,
+    /// Most lines wrapped text may take, instead of as many as fit the height
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<usize>,
+    /// Extra space in points after each character
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub letter_spacing: Option<f32>,
+    /// Number of equal cells to spread the text over, one character per cell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comb: Option<usize>,
+    /// Embedded TrueType/OpenType font: a file name in `--font-dir` or a path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    /// `left`, `center`, `right` or `justify` (wrapped lines fill the width)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub alignment: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub vertical_alignment: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub options: Option<Vec<String>>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub fit_mode: Option<ImageFitMode>,
-    #[serde(default)]
+    /// Clockwise image rotation in degrees, applied after EXIF orientation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f32>,
+    /// Part of the image to keep in frame when it is larger than the box
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focal_point: Option<FocalPoint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub text_overflow: Option<TextOverflow>,
+    /// Heading of the field's continuation sheet (defaults to `field_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Resample images larger than this resolution at their placed size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_dpi: Option<f32>,
+    /// JPEG quality (1-100) for re-encoded images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jpeg_quality: Option<u8>,
+    /// Text and check mark color: `#rrggbb`, `#rgb`, `rgb(r, g, b)` or
+    /// `cmyk(c, m, y, k)`. Other colors take the same forms
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Fill behind the field box
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    /// Black when only `border_width` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_color: Option<String>,
+    /// In points, drawn inside the box; 1 when only `border_color` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_width: Option<f32>,
+    /// 0 to 1, applied to everything the field draws
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f32>,
+    /// Synthesized by outlining the glyphs, so any font can be bold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    /// Synthesized by slanting the glyphs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FieldData {
+    /// Whether the field is drawn at its own box. Fields without one fill the
+    /// template's AcroForm field named by `field_id`.
+    pub fn has_position(&self) -> bool {
+        self.width > 0.0 && self.height > 0.0
+    }
+    
+    /// A field without coordinates that fills the template form field `field_id`.
+    pub fn named(field_id: impl Into<String>, value: FieldValue) -> Self {
+        Self {
+            field_id: field_id.into(),
+            value,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TextOverflow {
     /// Text can overflow beyond field boundaries (default)
+    #[default]
     Overflow,
     /// Text is truncated at field boundaries
     Cutoff,
+    /// Text shrinks to the largest size between `min_font_size` and
+    /// `font_size` at which it fits, on one line or wrapped
+    #[serde(rename = "auto_fit")]
+    AutoFit,
+    /// Text is laid out as with `Overflow` and clipped to the field box
+    Clip,
+    /// Text is truncated at field boundaries, ending in "…"
+    Ellipsis,
+    /// Like `AutoFit`, but each paragraph stays on one line
+    Shrink,
+    /// Text that doesn't fit goes on a continuation sheet appended to the
+    /// filled PDF, and the box ends with a marker pointing to it
+    Continue,
 }
 
-impl Default for TextOverflow {
-    fn default() -> Self {
-        Self::Overflow
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ImageFitMode {
     /// Stretch to fill (may distort)
     Fill,
     /// Fit within bounds (maintain aspect ratio, may have empty space)
+    #[default]
     Contain,
     /// Cover entire bounds (maintain aspect ratio, may crop)
     Cover,
@@ -85,10 +231,12 @@ pub enum ImageFitMode {
     ScaleDown,
 }
 
-impl Default for ImageFitMode {
-    fn default() -> Self {
-        Self::Contain
-    }
+/// A point of an upright image as fractions of its width and height,
+/// measured from the top-left corner (`{"x": 0.5, "y": 0.5}` is the center).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FocalPoint {
+    pub x: f32,
+    pub y: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,6 +250,147 @@ pub enum FieldValue {
     Dropdown(String),
     Image(ImageSource),
     Signature(ImageSource),
+    /// A typed name drawn in the field's `font`, usually a script font
+    #[serde(rename = "typed_signature")]
+    TypedSignature(TypedSignature),
+    Qrcode(QrCodeValue),
+    Barcode(BarcodeValue),
+    Line(LineShape),
+    Rect(RectShape),
+    Ellipse(EllipseShape),
+    Highlight(HighlightShape),
+}
+
+/// Colors take the same forms as `FieldData::color`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LineShape {
+    /// Black by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// In points (default 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_width: Option<f32>,
+    #[serde(default)]
+    pub direction: LineDirection,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineDirection {
+    /// Through the middle of the box, e.g. to strike text out (default)
+    #[default]
+    Horizontal,
+    Vertical,
+    /// From the top-left corner to the bottom-right
+    DiagonalDown,
+    /// From the bottom-left corner to the top-right
+    DiagonalUp,
+}
+
+/// Without either color the outline is stroked in black.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RectShape {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill_color: Option<String>,
+    /// In points (default 1), drawn inside the box
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_width: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corner_radius: Option<f32>,
+}
+
+/// Without either color the outline is stroked in black.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EllipseShape {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill_color: Option<String>,
+    /// In points (default 1), drawn inside the box
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_width: Option<f32>,
+}
+
+/// A translucent fill over the box, like a highlighter pen.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighlightShape {
+    /// Yellow (`#ffeb3b`) by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// 0 to 1 (default 0.35)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TypedSignature {
+    pub name: String,
+    /// Draw a signature line under the name
+    #[serde(default)]
+    pub underline: bool,
+    /// Add a "Digitally signed by {name} on {date}" caption below
+    #[serde(default)]
+    pub caption: bool,
+    /// Date shown in the caption; today's date (YYYY-MM-DD) by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_on: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QrCodeValue {
+    pub data: String,
+    #[serde(default)]
+    pub error_correction: ErrorCorrection,
+    /// Blank margin in modules (default 4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_zone: Option<u32>,
+    /// Print `data` under the code
+    #[serde(default)]
+    pub human_readable: bool,
+}
+
+/// Share of a QR code that can be damaged and still scan.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorCorrection {
+    /// About 7%
+    #[serde(alias = "l")]
+    Low,
+    /// About 15%
+    #[default]
+    #[serde(alias = "m")]
+    Medium,
+    /// About 25%
+    #[serde(alias = "q")]
+    Quartile,
+    /// About 30%
+    #[serde(alias = "h")]
+    High,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BarcodeValue {
+    pub data: String,
+    #[serde(default)]
+    pub symbology: Symbology,
+    /// Blank margin on each side, in narrow bar widths (default 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_zone: Option<u32>,
+    /// Print `data` under the bars
+    #[serde(default)]
+    pub human_readable: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Symbology {
+    /// Any printable ASCII text
+    #[default]
+    Code128,
+    /// 12 digits plus a check digit, which is computed when left out
+    Ean13,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -109,6 +398,42 @@ pub enum FieldValue {
 pub enum ImageSource {
     Base64(String),
     Url(UrlConfig),
+    /// Signature pad strokes, drawn as vector paths
+    Strokes(StrokeSignature),
+}
+
+/// Pen strokes captured by a signature pad, in the pad's coordinates (y down).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrokeSignature {
+    pub strokes: Vec<Vec<StrokePoint>>,
+    /// Pad size, placed like an image's pixel size; defaults to the strokes'
+    /// bounding box
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canvas: Option<CanvasSize>,
+    /// Black by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pen_color: Option<String>,
+    /// Line width in pad units, at full pressure when the width varies (default 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pen_width: Option<f32>,
+    /// Line width at zero pressure; setting it makes the width follow pressure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_pen_width: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StrokePoint {
+    pub x: f32,
+    pub y: f32,
+    /// 0 to 1; points without one draw at `pen_width`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressure: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CanvasSize {
+    pub width: f32,
+    pub height: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,22 +469,9 @@ pub fn extract_pdf_info(document: &Document) -> anyhow::Result<PdfDocument> {
         let page_dict = document.get_dictionary(*page_id)
             .map_err(|e| anyhow::anyhow!("Failed to get page dictionary: {}", e))?;
         
-        let (width, height) = if let Ok(media_box) = page_dict.get(b"MediaBox") {
-            if let lopdf::Object::Array(ref arr) = *media_box {
-                if arr.len() >= 4 {
-                    let x1 = arr[0].as_f32().unwrap_or(0.0);
-                    let y1 = arr[1].as_f32().unwrap_or(0.0);
-                    let x2 = arr[2].as_f32().unwrap_or(595.0);
-                    let y2 = arr[3].as_f32().unwrap_or(842.0);
-                    (x2 - x1, y2 - y1)
-                } else {
-                    (595.0, 842.0)
-                }
-            } else {
-                (595.0, 842.0)
-            }
-        } else {
-            (595.0, 842.0)
+        let (width, height) = match page_box(document, page_dict, b"MediaBox") {
+            Some([x1, y1, x2, y2]) => (x2 - x1, y2 - y1),
+            None => (595.0, 842.0),
         };
         
         page_infos.push(PdfPageInfo {
@@ -171,3 +483,29 @@ pub fn extract_pdf_info(document: &Document) -> anyhow::Result<PdfDocument> {
     
     Ok(PdfDocument { pages: page_infos })
 }
+
+/// Read a page box (`MediaBox`, `CropBox`, ...) as `[x1, y1, x2, y2]`.
+/// Boxes missing on the page itself are inherited from the page tree.
+pub fn page_box(document: &Document, page_dict: &lopdf::Dictionary, key: &[u8]) -> Option<[f32; 4]> {
+    let mut node = page_dict;
+    // Bounded walk up the page tree in case of malformed /Parent cycles
+    for _ in 0..32 {
+        if let Ok(value) = node.get(key) {
+            let arr = match value {
+                lopdf::Object::Reference(id) => document.get_object(*id).ok()?.as_array().ok()?,
+                other => other.as_array().ok()?,
+            };
+            if arr.len() < 4 {
+                return None;
+            }
+            let mut rect = [0.0; 4];
+            for (slot, obj) in rect.iter_mut().zip(arr.iter()) {
+                *slot = obj.as_float().ok()?;
+            }
+            return Some(rect);
+        }
+        let parent = node.get(b"Parent").and_then(lopdf::Object::as_reference).ok()?;
+        node = document.get_dictionary(parent).ok()?;
+    }
+    None
+}